@@ -0,0 +1,149 @@
+use crate::cpu::Interrupts;
+
+// Everything the CPU can reach. Reads and writes each take one M-cycle, during
+// which the rest of the system advances.
+pub trait Bus {
+    fn read(&mut self, address: u16) -> u8;
+    fn write(&mut self, address: u16, val: u8);
+    // An M-cycle the CPU spends without accessing memory
+    fn tick(&mut self);
+    // Read without taking any time, for debugging and low power mode
+    fn peek(&self, address: u16) -> u8;
+
+    fn pending_interrupts(&self) -> Interrupts;
+    fn reset_interrupt_flag(&mut self, interrupt: Interrupts);
+    // STOP resets the divider without a write to DIV
+    fn reset_div(&mut self);
+
+    fn read_pc(&mut self, pc: &mut u16) -> u8 {
+        let val = self.read(*pc);
+        *pc = pc.wrapping_add(1);
+        val
+    }
+
+    fn read_word(&mut self, address: u16) -> u16 {
+        let low = self.read(address);
+        let high = self.read(address.wrapping_add(1));
+        u16::from_le_bytes([low, high])
+    }
+
+    fn read_word_pc(&mut self, pc: &mut u16) -> u16 {
+        let val = self.read_word(*pc);
+        *pc = pc.wrapping_add(2);
+        val
+    }
+
+    fn write_word(&mut self, address: u16, val: u16) {
+        let [low, high] = val.to_le_bytes();
+        self.write(address, low);
+        self.write(address.wrapping_add(1), high);
+    }
+}
+
+// 64KiB of flat RAM with nothing else behind it, for running the CPU in
+// isolation. No interrupts are ever raised.
+pub struct RamBus {
+    memory: Box<[u8; 0x10000]>,
+}
+
+impl RamBus {
+    pub fn new() -> RamBus {
+        RamBus {
+            memory: Box::new([0; 0x10000]),
+        }
+    }
+
+    // Copy `data` into memory starting at `address`, wrapping at the end
+    pub fn load(&mut self, address: u16, data: &[u8]) {
+        for (i, val) in data.iter().enumerate() {
+            self.memory[address.wrapping_add(i as u16) as usize] = *val;
+        }
+    }
+}
+
+impl Default for RamBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bus for RamBus {
+    fn read(&mut self, address: u16) -> u8 {
+        self.memory[address as usize]
+    }
+
+    fn write(&mut self, address: u16, val: u8) {
+        self.memory[address as usize] = val
+    }
+
+    fn tick(&mut self) {}
+
+    fn peek(&self, address: u16) -> u8 {
+        self.memory[address as usize]
+    }
+
+    fn pending_interrupts(&self) -> Interrupts {
+        Interrupts::empty()
+    }
+
+    fn reset_interrupt_flag(&mut self, _interrupt: Interrupts) {}
+
+    fn reset_div(&mut self) {}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    Read(u16, u8),
+    Write(u16, u8),
+    // An M-cycle without a memory access
+    Internal,
+}
+
+// Logs what happens on another bus during every M-cycle
+pub struct Recorder<B: Bus> {
+    pub bus: B,
+    pub accesses: Vec<Access>,
+}
+
+impl<B: Bus> Recorder<B> {
+    pub fn new(bus: B) -> Recorder<B> {
+        Recorder {
+            bus,
+            accesses: Vec::new(),
+        }
+    }
+}
+
+impl<B: Bus> Bus for Recorder<B> {
+    fn read(&mut self, address: u16) -> u8 {
+        let val = self.bus.read(address);
+        self.accesses.push(Access::Read(address, val));
+        val
+    }
+
+    fn write(&mut self, address: u16, val: u8) {
+        self.bus.write(address, val);
+        self.accesses.push(Access::Write(address, val));
+    }
+
+    fn tick(&mut self) {
+        self.bus.tick();
+        self.accesses.push(Access::Internal);
+    }
+
+    fn peek(&self, address: u16) -> u8 {
+        self.bus.peek(address)
+    }
+
+    fn pending_interrupts(&self) -> Interrupts {
+        self.bus.pending_interrupts()
+    }
+
+    fn reset_interrupt_flag(&mut self, interrupt: Interrupts) {
+        self.bus.reset_interrupt_flag(interrupt)
+    }
+
+    fn reset_div(&mut self) {
+        self.bus.reset_div()
+    }
+}
@@ -1,23 +1,62 @@
+use crate::cpu::Cycles;
+use crate::mbc::camera::Camera;
+use crate::mbc::huc1::Huc1;
+use crate::mbc::huc3::{self, Huc3};
+use crate::mbc::mbc1::Mbc1;
+use crate::mbc::mbc2::{self, Mbc2};
+use crate::mbc::mbc3::Mbc3;
+use crate::mbc::mbc5::Mbc5;
+use crate::mbc::mbc7::{self, Mbc7};
+use crate::mbc::mmm01::Mmm01;
 use crate::mbc::no_mbc::NoMbc;
+use crate::mbc::rtc::Rtc;
 use crate::mbc::Mbc;
-use crate::rom_info::MbcType;
+use crate::rom_info::{MbcType, RomInfo};
 
 pub struct Cartridge {
     rom: Vec<u8>,
+    ram: Vec<u8>,
+    battery: bool,
+    // Battery backed RAM was written since the last save
+    dirty: bool,
     mbc: Box<dyn Mbc>,
 }
 
 impl Cartridge {
-    pub fn new(rom: Vec<u8>, mbc_type: MbcType) -> Cartridge {
-        let mbc: Box<dyn Mbc> = match mbc_type {
+    pub fn new(rom: Vec<u8>, info: &RomInfo) -> Cartridge {
+        let mut ram_size = info.ram_size;
+
+        let mbc: Box<dyn Mbc> = match info.mbc_type {
             MbcType::NoMBC => Box::new(NoMbc::new()),
+            MbcType::MBC1 => Box::new(Mbc1::new()),
+            MbcType::MBC1M => Box::new(Mbc1::multicart()),
+            MbcType::MBC2 => {
+                ram_size = mbc2::RAM_SIZE;
+                Box::new(Mbc2::new())
+            }
+            MbcType::MMM01 => Box::new(Mmm01::new()),
+            MbcType::MBC3 => Box::new(Mbc3::new(info.rtc)),
+            MbcType::MBC5 => Box::new(Mbc5::new(info.rumble)),
+            MbcType::MBC7 => {
+                ram_size = mbc7::EEPROM_SIZE;
+                Box::new(Mbc7::new())
+            }
+            MbcType::HuC1 => Box::new(Huc1::new()),
+            MbcType::HuC3 => Box::new(Huc3::new()),
+            MbcType::PocketCamera => Box::new(Camera::new()),
             _ => {
-                println!("Mbc {:?} not supported, continuing anyway..", mbc_type);
+                println!("Mbc {:?} not supported, continuing anyway..", info.mbc_type);
                 Box::new(NoMbc::new())
             }
         };
 
-        Cartridge { rom, mbc }
+        Cartridge {
+            rom,
+            ram: vec![0; ram_size],
+            battery: info.battery,
+            dirty: false,
+            mbc,
+        }
     }
 
     pub fn read(&self, address: u16) -> u8 {
@@ -27,4 +66,80 @@ impl Cartridge {
     pub fn write(&mut self, address: u16, val: u8) {
         self.mbc.write(address, val)
     }
+
+    pub fn read_ram(&self, address: u16) -> u8 {
+        self.mbc.read_ram(address, self.ram.as_slice())
+    }
+
+    pub fn write_ram(&mut self, address: u16, val: u8) {
+        if self.mbc.write_ram(address, val, self.ram.as_mut_slice()) {
+            self.dirty |= self.battery;
+        }
+    }
+
+    pub fn step(&mut self, cycles: Cycles) {
+        self.mbc.step(cycles)
+    }
+
+    pub fn rtc(&self) -> Option<&Rtc> {
+        self.mbc.rtc()
+    }
+
+    pub fn rtc_mut(&mut self) -> Option<&mut Rtc> {
+        self.mbc.rtc_mut()
+    }
+
+    pub fn huc3_clock(&self) -> Option<&huc3::Clock> {
+        self.mbc.huc3_clock()
+    }
+
+    pub fn huc3_clock_mut(&mut self) -> Option<&mut huc3::Clock> {
+        self.mbc.huc3_clock_mut()
+    }
+
+    // Whether there's a clock that needs saving along with RAM
+    pub fn has_clock(&self) -> bool {
+        self.rtc().is_some() || self.huc3_clock().is_some()
+    }
+
+    pub fn rumbling(&self) -> bool {
+        self.mbc.rumbling()
+    }
+
+    pub fn infrared_led(&self) -> bool {
+        self.mbc.infrared_led()
+    }
+
+    pub fn receive_infrared(&mut self, light: bool) {
+        self.mbc.receive_infrared(light)
+    }
+
+    pub fn set_accelerometer(&mut self, x: f32, y: f32) {
+        self.mbc.set_accelerometer(x, y)
+    }
+
+    pub fn camera_mut(&mut self) -> Option<&mut Camera> {
+        self.mbc.camera_mut()
+    }
+
+    // The RAM that's kept when the power is off, if any
+    pub fn battery_ram(&self) -> Option<&[u8]> {
+        if self.battery && !self.ram.is_empty() {
+            Some(&self.ram)
+        } else {
+            None
+        }
+    }
+
+    // Whether battery backed RAM needs saving, clearing the flag
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    // Restore battery backed RAM from a previous session. Data of the wrong
+    // size is copied as far as it fits.
+    pub fn load_battery_ram(&mut self, data: &[u8]) {
+        let len = data.len().min(self.ram.len());
+        self.ram[..len].copy_from_slice(&data[..len]);
+    }
 }
@@ -1,9 +1,6 @@
-use crate::joypad::Joypad;
-use crate::mmu::Mapper;
-use crate::mmu::Mmu;
+use crate::bus::Bus;
+use crate::instruction::{Condition, Instruction, Mnemonic, Operand};
 use crate::ops::*;
-use crate::timers::timers::Timers;
-use crate::video::Video;
 use bitflags::bitflags;
 use std::fmt;
 
@@ -19,13 +16,37 @@ pub struct Cpu {
     sp: u16,
     pc: u16,
     ime: bool,
+    ime_scheduled: bool,
 
     halted: bool,
+    halt_bug: bool,
+    stopped: bool,
+    locked: bool,
+
+    // Set when LD B,B is executed, which test ROMs use as a breakpoint
+    breakpoint: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Registers {
+    pub a: u8,
+    pub f: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8,
+    pub sp: u16,
+    pub pc: u16,
 }
 
-#[derive(Clone, Copy, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
 pub struct Cycles(pub u32);
 
+// T-cycles per second
+pub const CLOCK_SPEED: Cycles = Cycles(4_194_304);
+
 impl std::ops::Add for Cycles {
     type Output = Self;
 
@@ -79,6 +100,26 @@ bitflags! {
     }
 }
 
+impl Interrupts {
+    pub const SOURCES: Interrupts = Interrupts::from_bits_retain(0b00011111);
+
+    // In priority order, highest first
+    const VECTORS: [(Interrupts, u16); 5] = [
+        (Interrupts::VBLANK, 0x40),
+        (Interrupts::LCD, 0x48),
+        (Interrupts::TIMER, 0x50),
+        (Interrupts::SERIAL, 0x58),
+        (Interrupts::JOYPAD, 0x60),
+    ];
+
+    pub fn highest_priority(&self) -> Option<(Interrupts, u16)> {
+        Self::VECTORS
+            .iter()
+            .find(|(interrupt, _)| self.contains(*interrupt))
+            .copied()
+    }
+}
+
 impl Cpu {
     pub fn new(checksum: u8) -> Cpu {
         Cpu {
@@ -97,446 +138,602 @@ impl Cpu {
             sp: 0xfffe,
             pc: 0x0100,
             ime: false,
+            ime_scheduled: false,
             halted: false,
+            halt_bug: false,
+            stopped: false,
+            locked: false,
+            breakpoint: false,
         }
     }
 
-    pub fn step(
-        &mut self,
-        mmu: &mut Mmu,
-        video: &mut Video,
-        timers: &mut Timers,
-        joypad: &mut Joypad,
-    ) -> Cycles {
+    // Run the next instruction, or idle for an M-cycle while halted
+    pub fn step<B: Bus>(&mut self, bus: &mut B) -> Cycles {
+        let bus = &mut Clocked {
+            bus,
+            elapsed: Cycles(0),
+        };
+
+        if self.locked {
+            bus.tick();
+            return bus.elapsed();
+        }
+
+        if self.stopped {
+            // Low power mode is only left when a selected joypad line goes
+            // low. The system clock is halted entirely until then.
+            if bus.peek(0xff00) & 0x0f == 0x0f {
+                return Cycles(4);
+            }
+            self.stopped = false;
+        }
+
         if self.ime || self.halted {
-            let interrupts = mmu.interrupt_flags().intersection(mmu.enabled_interrupts());
+            let interrupts = bus.pending_interrupts();
             if !interrupts.is_empty() {
-                if self.halted {
-                    println!("resuming..");
-                    self.halted = false;
-                }
+                self.halted = false;
 
                 if self.ime {
-                    self.ime = false;
-                    self.sp -= 2;
-                    mmu.write_word(self.sp, self.pc, video, timers, joypad);
-
-                    if interrupts.contains(Interrupts::VBLANK) {
-                        self.pc = 0x40;
-                        mmu.reset_interrupt_flag(Interrupts::VBLANK);
-                        println!("vblank interrupt!");
-                    } else if interrupts.contains(Interrupts::SERIAL) {
-                        self.pc = 0x58;
-                        mmu.reset_interrupt_flag(Interrupts::SERIAL);
-                        println!("serial interrupt!");
-                    } else {
-                        panic!("unhandled interrupt {:?}", interrupts)
-                    }
-
-                    return Cycles(20);
+                    self.dispatch_interrupt(bus);
+                    return bus.elapsed();
                 }
             }
         }
 
         if self.halted {
-            return Cycles(4);
+            bus.tick();
+            return bus.elapsed();
+        }
+
+        // EI only takes effect after the instruction following it
+        let enable_interrupts = self.ime_scheduled;
+
+        let opcode = if self.halt_bug {
+            // The HALT bug fails to increment PC, so this byte is read twice
+            self.halt_bug = false;
+            bus.read(self.pc)
+        } else {
+            bus.read_pc(&mut self.pc)
+        };
+        if opcode == 0x40 {
+            self.breakpoint = true;
+        }
+        let (instruction, immediate) = if opcode == 0xcb {
+            (Instruction::decode_cb(bus.read_pc(&mut self.pc)), 0)
+        } else {
+            let instruction = Instruction::decode(opcode);
+            // Any operand bytes directly follow the opcode. For STOP this is
+            // a padding byte that's ignored.
+            let immediate = match instruction.length {
+                2 => bus.read_pc(&mut self.pc) as u16,
+                3 => bus.read_word_pc(&mut self.pc),
+                _ => 0,
+            };
+            (instruction, immediate)
+        };
+        let cycles = self.execute(instruction, immediate, bus);
+
+        // Whatever isn't spent on memory accesses is internal delay
+        debug_assert!(bus.elapsed() <= cycles, "{} overran", instruction);
+        while bus.elapsed() < cycles {
+            bus.tick();
+        }
+
+        if enable_interrupts && self.ime_scheduled {
+            self.ime = true;
+            self.ime_scheduled = false;
+        }
+
+        cycles
+    }
+
+    pub fn registers(&self) -> Registers {
+        Registers {
+            a: self.a,
+            f: self.f.bits(),
+            b: self.b,
+            c: self.c,
+            d: self.d,
+            e: self.e,
+            h: self.h,
+            l: self.l,
+            sp: self.sp,
+            pc: self.pc,
+        }
+    }
+
+    pub fn set_registers(&mut self, registers: Registers) {
+        self.a = registers.a;
+        self.f = Flags::from_bits_retain(registers.f & 0xf0);
+        self.b = registers.b;
+        self.c = registers.c;
+        self.d = registers.d;
+        self.e = registers.e;
+        self.h = registers.h;
+        self.l = registers.l;
+        self.sp = registers.sp;
+        self.pc = registers.pc;
+    }
+
+    pub fn ime(&self) -> bool {
+        self.ime
+    }
+
+    pub fn set_ime(&mut self, ime: bool) {
+        self.ime = ime;
+    }
+
+    // Whether EI was just executed, and IME will be set after the next
+    // instruction
+    pub fn ime_scheduled(&self) -> bool {
+        self.ime_scheduled
+    }
+
+    // Whether an illegal opcode has hung the CPU
+    pub fn locked(&self) -> bool {
+        self.locked
+    }
+
+    // Whether LD B,B was executed since the last call
+    pub fn take_breakpoint(&mut self) -> bool {
+        std::mem::take(&mut self.breakpoint)
+    }
+
+    // The state before the next instruction in gameboy-doctor format, or None
+    // if the next step won't execute one
+    pub fn trace(&self, bus: &impl Bus) -> Option<String> {
+        let pending = bus.pending_interrupts();
+        if self.locked
+            || self.stopped
+            || (self.halted && pending.is_empty())
+            || (self.ime && !pending.is_empty())
+        {
+            return None;
         }
 
-        let mapper = &mut Mapper::new(mmu, video, timers, joypad);
-        let instruction = mapper.read_pc(&mut self.pc);
-        match instruction {
-            // 8-bit load
-            0x40 => Cycles(4), // ld b,b
-            0x41 => ld_r_r(&mut self.b, self.c),
-            0x42 => ld_r_r(&mut self.b, self.d),
-            0x43 => ld_r_r(&mut self.b, self.e),
-            0x44 => ld_r_r(&mut self.b, self.h),
-            0x45 => ld_r_r(&mut self.b, self.l),
-            0x47 => ld_r_r(&mut self.b, self.a),
-            0x48 => ld_r_r(&mut self.c, self.b),
-            0x49 => Cycles(4), // ld c,c
-            0x4a => ld_r_r(&mut self.c, self.d),
-            0x4b => ld_r_r(&mut self.c, self.e),
-            0x4c => ld_r_r(&mut self.c, self.h),
-            0x4d => ld_r_r(&mut self.c, self.l),
-            0x4f => ld_r_r(&mut self.c, self.a),
-            0x50 => ld_r_r(&mut self.d, self.b),
-            0x51 => ld_r_r(&mut self.d, self.c),
-            0x52 => Cycles(4), // ld d,d
-            0x53 => ld_r_r(&mut self.d, self.e),
-            0x54 => ld_r_r(&mut self.d, self.h),
-            0x55 => ld_r_r(&mut self.d, self.l),
-            0x57 => ld_r_r(&mut self.d, self.a),
-            0x58 => ld_r_r(&mut self.e, self.b),
-            0x59 => ld_r_r(&mut self.e, self.c),
-            0x5a => ld_r_r(&mut self.e, self.d),
-            0x5b => Cycles(4), // ld e,e
-            0x5c => ld_r_r(&mut self.e, self.h),
-            0x5d => ld_r_r(&mut self.e, self.l),
-            0x5f => ld_r_r(&mut self.e, self.a),
-            0x60 => ld_r_r(&mut self.h, self.b),
-            0x61 => ld_r_r(&mut self.h, self.c),
-            0x62 => ld_r_r(&mut self.h, self.d),
-            0x63 => ld_r_r(&mut self.h, self.e),
-            0x64 => Cycles(4), // ld h,h
-            0x65 => ld_r_r(&mut self.h, self.l),
-            0x67 => ld_r_r(&mut self.h, self.a),
-            0x68 => ld_r_r(&mut self.l, self.b),
-            0x69 => ld_r_r(&mut self.l, self.c),
-            0x6a => ld_r_r(&mut self.l, self.d),
-            0x6b => ld_r_r(&mut self.l, self.e),
-            0x6c => ld_r_r(&mut self.l, self.h),
-            0x6d => Cycles(4), // ld l,l
-            0x6f => ld_r_r(&mut self.l, self.a),
-            0x78 => ld_r_r(&mut self.a, self.b),
-            0x79 => ld_r_r(&mut self.a, self.c),
-            0x7a => ld_r_r(&mut self.a, self.d),
-            0x7b => ld_r_r(&mut self.a, self.e),
-            0x7c => ld_r_r(&mut self.a, self.h),
-            0x7d => ld_r_r(&mut self.a, self.l),
-            0x7f => Cycles(4), // ld a,a
-            0x06 => ld_r_n(&mut self.b, mapper.read_pc(&mut self.pc)),
-            0x0e => ld_r_n(&mut self.c, mapper.read_pc(&mut self.pc)),
-            0x16 => ld_r_n(&mut self.d, mapper.read_pc(&mut self.pc)),
-            0x1e => ld_r_n(&mut self.e, mapper.read_pc(&mut self.pc)),
-            0x26 => ld_r_n(&mut self.h, mapper.read_pc(&mut self.pc)),
-            0x2e => ld_r_n(&mut self.l, mapper.read_pc(&mut self.pc)),
-            0x3e => ld_r_n(&mut self.a, mapper.read_pc(&mut self.pc)),
-            0x46 => ld_r_rrptr(&mut self.b, self.h, self.l, mapper),
-            0x4e => ld_r_rrptr(&mut self.c, self.h, self.l, mapper),
-            0x56 => ld_r_rrptr(&mut self.d, self.h, self.l, mapper),
-            0x5e => ld_r_rrptr(&mut self.e, self.h, self.l, mapper),
-            0x66 => {
-                let h = self.h;
-                ld_r_rrptr(&mut self.h, h, self.l, mapper)
-            }
-            0x6e => {
-                let l = self.l;
-                ld_r_rrptr(&mut self.l, self.h, l, mapper)
-            }
-            0x7e => ld_r_rrptr(&mut self.a, self.h, self.l, mapper),
-            0x70 => ld_hlptr_r(self.h, self.l, self.b, mapper),
-            0x71 => ld_hlptr_r(self.h, self.l, self.c, mapper),
-            0x72 => ld_hlptr_r(self.h, self.l, self.d, mapper),
-            0x73 => ld_hlptr_r(self.h, self.l, self.e, mapper),
-            0x74 => ld_hlptr_r(self.h, self.l, self.h, mapper),
-            0x75 => ld_hlptr_r(self.h, self.l, self.l, mapper),
-            0x77 => ld_hlptr_r(self.h, self.l, self.a, mapper),
-            0x36 => ld_hlptr_n(self.h, self.l, mapper.read_pc(&mut self.pc), mapper),
-            0x0a => ld_r_rrptr(&mut self.a, self.b, self.c, mapper),
-            0x1a => ld_r_rrptr(&mut self.a, self.d, self.e, mapper),
-            0xfa => ld_a_nnptr(&mut self.a, mapper.read_word_pc(&mut self.pc), mapper),
-            0x02 => ld_rrptr_a(self.b, self.c, self.a, mapper),
-            0x12 => ld_rrptr_a(self.d, self.e, self.a, mapper),
-            0xea => ld_nnptr_a(mapper.read_word_pc(&mut self.pc), self.a, mapper),
-            0xf0 => ld_a_nhptr(&mut self.a, mapper.read_pc(&mut self.pc), mapper),
-            0xe0 => ld_nhptr_a(mapper.read_pc(&mut self.pc), self.a, mapper),
-            0xf2 => ld_a_chptr(&mut self.a, self.c, mapper),
-            0xe2 => ld_chptr_a(self.c, self.a, mapper),
-            0x22 => ld_hlptr_inc_a(&mut self.h, &mut self.l, self.a, mapper),
-            0x2a => ld_a_hlptr_inc(&mut self.a, &mut self.h, &mut self.l, mapper),
-            0x32 => ld_hlptr_dec_a(&mut self.h, &mut self.l, self.a, mapper),
-            0x3a => ld_a_hlptr_dec(&mut self.a, &mut self.h, &mut self.l, mapper),
-
-            // 16-bit load
-            0x01 => ld_rr_nn(&mut self.b, &mut self.c, mapper.read_word_pc(&mut self.pc)),
-            0x11 => ld_rr_nn(&mut self.d, &mut self.e, mapper.read_word_pc(&mut self.pc)),
-            0x21 => ld_rr_nn(&mut self.h, &mut self.l, mapper.read_word_pc(&mut self.pc)),
-            0x31 => ld_sp_nn(&mut self.sp, mapper.read_word_pc(&mut self.pc)),
-            0x08 => ld_nnptr_sp(mapper.read_word_pc(&mut self.pc), self.sp, mapper),
-            0xf9 => ld_sp_hl(&mut self.sp, self.h, self.l),
-            0xc5 => push_rr(self.b, self.c, &mut self.sp, mapper),
-            0xd5 => push_rr(self.d, self.e, &mut self.sp, mapper),
-            0xe5 => push_rr(self.h, self.l, &mut self.sp, mapper),
-            0xf5 => push_rr(self.a, self.f.bits(), &mut self.sp, mapper),
-            0xc1 => pop_rr(&mut self.b, &mut self.c, &mut self.sp, mapper),
-            0xd1 => pop_rr(&mut self.d, &mut self.e, &mut self.sp, mapper),
-            0xe1 => pop_rr(&mut self.h, &mut self.l, &mut self.sp, mapper),
-            0xf1 => pop_af(&mut self.a, &mut self.f, &mut self.sp, mapper),
-
-            // 8-bit arithmetic and logic
-            0x80 => add_a_r(&mut self.a, self.b, &mut self.f),
-            0x81 => add_a_r(&mut self.a, self.c, &mut self.f),
-            0x82 => add_a_r(&mut self.a, self.d, &mut self.f),
-            0x83 => add_a_r(&mut self.a, self.e, &mut self.f),
-            0x84 => add_a_r(&mut self.a, self.h, &mut self.f),
-            0x85 => add_a_r(&mut self.a, self.l, &mut self.f),
-            0x87 => {
-                let a = self.a;
-                add_a_r(&mut self.a, a, &mut self.f)
-            }
-            0xc6 => add_a_n(&mut self.a, mapper.read_pc(&mut self.pc), &mut self.f),
-            0x86 => add_a_hlptr(&mut self.a, self.h, self.l, &mut self.f, mapper),
-            0x88 => adc_a_r(&mut self.a, self.b, &mut self.f),
-            0x89 => adc_a_r(&mut self.a, self.c, &mut self.f),
-            0x8a => adc_a_r(&mut self.a, self.d, &mut self.f),
-            0x8b => adc_a_r(&mut self.a, self.e, &mut self.f),
-            0x8c => adc_a_r(&mut self.a, self.h, &mut self.f),
-            0x8d => adc_a_r(&mut self.a, self.l, &mut self.f),
-            0x8f => {
-                let a = self.a;
-                adc_a_r(&mut self.a, a, &mut self.f)
-            }
-            0xce => adc_a_n(&mut self.a, mapper.read_pc(&mut self.pc), &mut self.f),
-            0x8e => adc_a_hlptr(&mut self.a, self.h, self.l, &mut self.f, mapper),
-            0x90 => sub_r(&mut self.a, self.b, &mut self.f),
-            0x91 => sub_r(&mut self.a, self.c, &mut self.f),
-            0x92 => sub_r(&mut self.a, self.d, &mut self.f),
-            0x93 => sub_r(&mut self.a, self.e, &mut self.f),
-            0x94 => sub_r(&mut self.a, self.h, &mut self.f),
-            0x95 => sub_r(&mut self.a, self.l, &mut self.f),
-            0x97 => {
-                let a = self.a;
-                sub_r(&mut self.a, a, &mut self.f)
-            }
-            0xd6 => sub_n(&mut self.a, mapper.read_pc(&mut self.pc), &mut self.f),
-            0x96 => sub_hlptr(&mut self.a, self.h, self.l, &mut self.f, mapper),
-            0x98 => sbc_a_r(&mut self.a, self.b, &mut self.f),
-            0x99 => sbc_a_r(&mut self.a, self.c, &mut self.f),
-            0x9a => sbc_a_r(&mut self.a, self.d, &mut self.f),
-            0x9b => sbc_a_r(&mut self.a, self.e, &mut self.f),
-            0x9c => sbc_a_r(&mut self.a, self.h, &mut self.f),
-            0x9d => sbc_a_r(&mut self.a, self.l, &mut self.f),
-            0x9f => {
-                let a = self.a;
-                sbc_a_r(&mut self.a, a, &mut self.f)
-            }
-            0xde => sbc_a_n(&mut self.a, mapper.read_pc(&mut self.pc), &mut self.f),
-            0x9e => sbc_a_hlptr(&mut self.a, self.h, self.l, &mut self.f, mapper),
-            0xa0 => and_r(&mut self.a, self.b, &mut self.f),
-            0xa1 => and_r(&mut self.a, self.c, &mut self.f),
-            0xa2 => and_r(&mut self.a, self.d, &mut self.f),
-            0xa3 => and_r(&mut self.a, self.e, &mut self.f),
-            0xa4 => and_r(&mut self.a, self.h, &mut self.f),
-            0xa5 => and_r(&mut self.a, self.l, &mut self.f),
-            0xa7 => {
-                let a = self.a;
-                and_r(&mut self.a, a, &mut self.f)
-            }
-            0xe6 => and_n(&mut self.a, mapper.read_pc(&mut self.pc), &mut self.f),
-            0xa6 => and_hlptr(&mut self.a, self.h, self.l, &mut self.f, mapper),
-            0xa8 => xor_r(&mut self.a, self.b, &mut self.f),
-            0xa9 => xor_r(&mut self.a, self.c, &mut self.f),
-            0xaa => xor_r(&mut self.a, self.d, &mut self.f),
-            0xab => xor_r(&mut self.a, self.e, &mut self.f),
-            0xac => xor_r(&mut self.a, self.h, &mut self.f),
-            0xad => xor_r(&mut self.a, self.l, &mut self.f),
-            0xaf => {
-                let a = self.a;
-                xor_r(&mut self.a, a, &mut self.f)
-            }
-            0xee => xor_n(&mut self.a, mapper.read_pc(&mut self.pc), &mut self.f),
-            0xae => xor_hlptr(&mut self.a, self.h, self.l, &mut self.f, mapper),
-            0xb0 => or_r(&mut self.a, self.b, &mut self.f),
-            0xb1 => or_r(&mut self.a, self.c, &mut self.f),
-            0xb2 => or_r(&mut self.a, self.d, &mut self.f),
-            0xb3 => or_r(&mut self.a, self.e, &mut self.f),
-            0xb4 => or_r(&mut self.a, self.h, &mut self.f),
-            0xb5 => or_r(&mut self.a, self.l, &mut self.f),
-            0xb7 => {
-                let a = self.a;
-                or_r(&mut self.a, a, &mut self.f)
-            }
-            0xf6 => or_n(&mut self.a, mapper.read_pc(&mut self.pc), &mut self.f),
-            0xb6 => or_hlptr(&mut self.a, self.h, self.l, &mut self.f, mapper),
-            0xb8 => cp_r(self.a, self.b, &mut self.f),
-            0xb9 => cp_r(self.a, self.c, &mut self.f),
-            0xba => cp_r(self.a, self.d, &mut self.f),
-            0xbb => cp_r(self.a, self.e, &mut self.f),
-            0xbc => cp_r(self.a, self.h, &mut self.f),
-            0xbd => cp_r(self.a, self.b, &mut self.f),
-            0xbf => cp_r(self.a, self.a, &mut self.f),
-            0xfe => cp_n(self.a, mapper.read_pc(&mut self.pc), &mut self.f),
-            0xbe => cp_hlptr(self.a, self.h, self.l, &mut self.f, mapper),
-            0x04 => inc_r(&mut self.b, &mut self.f),
-            0x0c => inc_r(&mut self.c, &mut self.f),
-            0x14 => inc_r(&mut self.d, &mut self.f),
-            0x1c => inc_r(&mut self.e, &mut self.f),
-            0x24 => inc_r(&mut self.h, &mut self.f),
-            0x2c => inc_r(&mut self.l, &mut self.f),
-            0x3c => inc_r(&mut self.a, &mut self.f),
-            0x34 => inc_hlptr(self.h, self.l, &mut self.f, mapper),
-            0x05 => dec_r(&mut self.b, &mut self.f),
-            0x0d => dec_r(&mut self.c, &mut self.f),
-            0x15 => dec_r(&mut self.d, &mut self.f),
-            0x1d => dec_r(&mut self.e, &mut self.f),
-            0x25 => dec_r(&mut self.h, &mut self.f),
-            0x2d => dec_r(&mut self.l, &mut self.f),
-            0x3d => dec_r(&mut self.a, &mut self.f),
-            0x35 => dec_hlptr(self.h, self.l, &mut self.f, mapper),
-            0x27 => daa(&mut self.a, &mut self.f),
-            0x2f => cpl(&mut self.a, &mut self.f),
-
-            // 16-bit arithmetic and logic
-            0x09 => add_hl_rr(&mut self.h, &mut self.l, self.b, self.c, &mut self.f),
-            0x19 => add_hl_rr(&mut self.h, &mut self.l, self.d, self.e, &mut self.f),
-            0x29 => {
-                let (h, l) = (self.h, self.l);
-                add_hl_rr(&mut self.h, &mut self.l, h, l, &mut self.f)
-            }
-            0x39 => add_hl_sp(&mut self.h, &mut self.l, self.sp, &mut self.f),
-            0x03 => inc_rr(&mut self.b, &mut self.c),
-            0x13 => inc_rr(&mut self.d, &mut self.e),
-            0x23 => inc_rr(&mut self.h, &mut self.l),
-            0x33 => inc_sp(&mut self.sp),
-            0x0b => dec_rr(&mut self.b, &mut self.c),
-            0x1b => dec_rr(&mut self.d, &mut self.e),
-            0x2b => dec_rr(&mut self.h, &mut self.l),
-            0x3b => dec_sp(&mut self.sp),
-            0xe8 => add_sp_dd(
-                &mut self.sp,
-                mapper.read_pc(&mut self.pc) as i8,
-                &mut self.f,
-            ),
-            0xf8 => ld_hl_sp_dd(
-                &mut self.h,
-                &mut self.l,
-                self.sp,
-                mapper.read_pc(&mut self.pc) as i8,
-                &mut self.f,
-            ),
-
-            // rotate and shift
-            0x07 => rlca(&mut self.a, &mut self.f),
-            0x17 => rla(&mut self.a, &mut self.f),
-            0x0f => rrca(&mut self.a, &mut self.f),
-            0x1f => rra(&mut self.a, &mut self.f),
-
-            // cpu control
-            0x00 => nop(),
-            0x76 => {
-                println!("halted!");
-                self.halted = true;
-                Cycles(4)
-            }
-            0xf3 => di(&mut self.ime),
-            0xfb => ei(&mut self.ime),
-
-            // jump
-            0xc3 => {
-                let nn = mapper.read_word_pc(&mut self.pc);
-                jp_nn(&mut self.pc, nn)
-            }
-            0xe9 => jp_hl(&mut self.pc, self.h, self.l),
-
-            0xc2 => {
-                let nn = mapper.read_word_pc(&mut self.pc);
-                jp_f_nn(&mut self.pc, !self.f.contains(Flags::Z), nn)
-            }
-            0xca => {
-                let nn = mapper.read_word_pc(&mut self.pc);
-                jp_f_nn(&mut self.pc, self.f.contains(Flags::Z), nn)
-            }
-            0xd2 => {
-                let nn = mapper.read_word_pc(&mut self.pc);
-                jp_f_nn(&mut self.pc, !self.f.contains(Flags::C), nn)
-            }
-            0xda => {
-                let nn = mapper.read_word_pc(&mut self.pc);
-                jp_f_nn(&mut self.pc, self.f.contains(Flags::C), nn)
-            }
-
-            0x18 => {
-                let distance = mapper.read_pc(&mut self.pc);
-                jr(&mut self.pc, distance)
-            }
-            0x20 => {
-                let distance = mapper.read_pc(&mut self.pc);
-                jr_if(&mut self.pc, distance, !self.f.contains(Flags::Z))
-            }
-            0x28 => {
-                let distance = mapper.read_pc(&mut self.pc);
-                jr_if(&mut self.pc, distance, self.f.contains(Flags::Z))
-            }
-            0x30 => {
-                let distance = mapper.read_pc(&mut self.pc);
-                jr_if(&mut self.pc, distance, !self.f.contains(Flags::C))
-            }
-            0x38 => {
-                let distance = mapper.read_pc(&mut self.pc);
-                jr_if(&mut self.pc, distance, self.f.contains(Flags::C))
-            }
-
-            0xcd => {
-                let nn = mapper.read_word_pc(&mut self.pc);
-                call_nn(&mut self.pc, &mut self.sp, nn, mapper)
-            }
-            0xc4 => {
-                let nn = mapper.read_word_pc(&mut self.pc);
-                call_f_nn(
-                    &mut self.pc,
-                    &mut self.sp,
-                    !self.f.contains(Flags::Z),
-                    nn,
-                    mapper,
-                )
-            }
-            0xc9 => ret(&mut self.pc, &mut self.sp, mapper),
-            0xc0 => ret_f(
-                &mut self.pc,
-                &mut self.sp,
-                !self.f.contains(Flags::N),
-                mapper,
-            ),
-            0xc8 => ret_f(
-                &mut self.pc,
-                &mut self.sp,
-                self.f.contains(Flags::Z),
-                mapper,
-            ),
-            0xd0 => ret_f(
-                &mut self.pc,
-                &mut self.sp,
-                !self.f.contains(Flags::C),
-                mapper,
-            ),
-            0xd8 => ret_f(
-                &mut self.pc,
-                &mut self.sp,
-                self.f.contains(Flags::C),
-                mapper,
-            ),
-            0xd9 => reti(&mut self.pc, &mut self.sp, &mut self.ime, mapper),
-            0xc7 => rst_n(&mut self.pc, &mut self.sp, 0x00, mapper),
-            0xcf => rst_n(&mut self.pc, &mut self.sp, 0x08, mapper),
-            0xd7 => rst_n(&mut self.pc, &mut self.sp, 0x10, mapper),
-            0xdf => rst_n(&mut self.pc, &mut self.sp, 0x18, mapper),
-            0xe7 => rst_n(&mut self.pc, &mut self.sp, 0x20, mapper),
-            0xef => rst_n(&mut self.pc, &mut self.sp, 0x28, mapper),
-            0xf7 => rst_n(&mut self.pc, &mut self.sp, 0x30, mapper),
-            0xff => rst_n(&mut self.pc, &mut self.sp, 0x38, mapper),
-
-            0xcb => {
-                let cb_instruction = mapper.read_pc(&mut self.pc);
-                match cb_instruction {
-                    0x30 => swap_r(&mut self.b),
-                    0x31 => swap_r(&mut self.c),
-                    0x32 => swap_r(&mut self.d),
-                    0x33 => swap_r(&mut self.e),
-                    0x34 => swap_r(&mut self.h),
-                    0x35 => swap_r(&mut self.l),
-                    0x37 => swap_r(&mut self.a),
-
-                    0xcf => set_n_r(1, &mut self.a),
-
-                    0x87 => res_n_r(0, &mut self.a),
-                    _ => {
-                        panic!(
-                            "Unimplemented instruction cb{:2x} at {:4x}",
-                            cb_instruction, self.pc
-                        );
+        let pcmem = [0, 1, 2, 3].map(|i| bus.peek(self.pc.wrapping_add(i)));
+        Some(format!(
+            "A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X}",
+            self.a,
+            self.f.bits(),
+            self.b,
+            self.c,
+            self.d,
+            self.e,
+            self.h,
+            self.l,
+            self.sp,
+            self.pc,
+            pcmem[0],
+            pcmem[1],
+            pcmem[2],
+            pcmem[3]
+        ))
+    }
+
+    fn execute(&mut self, instruction: &Instruction, immediate: u16, bus: &mut impl Bus) -> Cycles {
+        let [first, second] = instruction.operands;
+
+        let branched = match instruction.mnemonic {
+            Mnemonic::Nop | Mnemonic::Prefix => false,
+
+            Mnemonic::Ld | Mnemonic::Ldh => {
+                let (dst, src) = (first.unwrap(), second.unwrap());
+                if dst.is_register_pair() {
+                    let val = match src {
+                        Operand::SpOffset => sp_plus_dd(self.sp, immediate as i8, &mut self.f),
+                        _ => self.read_pair(src, immediate),
+                    };
+                    self.write_pair(dst, val);
+                } else if src.is_register_pair() {
+                    bus.write_word(immediate, self.read_pair(src, immediate));
+                } else {
+                    let val = self.read_operand(src, immediate, bus);
+                    self.write_operand(dst, immediate, val, bus);
+                }
+                false
+            }
+            Mnemonic::Push => {
+                let val = self.read_pair(first.unwrap(), immediate);
+                push(&mut self.sp, val, bus);
+                false
+            }
+            Mnemonic::Pop => {
+                let val = pop(&mut self.sp, bus);
+                self.write_pair(first.unwrap(), val);
+                false
+            }
+
+            Mnemonic::Add if first == Some(Operand::Hl) => {
+                let mut hl = self.read_pair(Operand::Hl, immediate);
+                add_hl(
+                    &mut hl,
+                    self.read_pair(second.unwrap(), immediate),
+                    &mut self.f,
+                );
+                self.write_pair(Operand::Hl, hl);
+                false
+            }
+            Mnemonic::Add if first == Some(Operand::Sp) => {
+                self.sp = sp_plus_dd(self.sp, immediate as i8, &mut self.f);
+                false
+            }
+            Mnemonic::Add
+            | Mnemonic::Adc
+            | Mnemonic::Sub
+            | Mnemonic::Sbc
+            | Mnemonic::And
+            | Mnemonic::Xor
+            | Mnemonic::Or
+            | Mnemonic::Cp => {
+                let op: fn(&mut u8, u8, &mut Flags) = match instruction.mnemonic {
+                    Mnemonic::Add => add,
+                    Mnemonic::Adc => adc,
+                    Mnemonic::Sub => sub,
+                    Mnemonic::Sbc => sbc,
+                    Mnemonic::And => and,
+                    Mnemonic::Xor => xor,
+                    Mnemonic::Or => or,
+                    _ => cp,
+                };
+                let val = self.read_operand(instruction.source().unwrap(), immediate, bus);
+                op(&mut self.a, val, &mut self.f);
+                false
+            }
+            Mnemonic::Inc | Mnemonic::Dec if first.unwrap().is_register_pair() => {
+                let val = self.read_pair(first.unwrap(), immediate);
+                self.write_pair(
+                    first.unwrap(),
+                    if instruction.mnemonic == Mnemonic::Inc {
+                        val.wrapping_add(1)
+                    } else {
+                        val.wrapping_sub(1)
+                    },
+                );
+                false
+            }
+            Mnemonic::Inc => {
+                self.modify_operand(first.unwrap(), bus, inc);
+                false
+            }
+            Mnemonic::Dec => {
+                self.modify_operand(first.unwrap(), bus, dec);
+                false
+            }
+            Mnemonic::Daa => {
+                daa(&mut self.a, &mut self.f);
+                false
+            }
+            Mnemonic::Cpl => {
+                cpl(&mut self.a, &mut self.f);
+                false
+            }
+            Mnemonic::Scf => {
+                scf(&mut self.f);
+                false
+            }
+            Mnemonic::Ccf => {
+                ccf(&mut self.f);
+                false
+            }
+
+            Mnemonic::Rlca => {
+                rlca(&mut self.a, &mut self.f);
+                false
+            }
+            Mnemonic::Rla => {
+                rla(&mut self.a, &mut self.f);
+                false
+            }
+            Mnemonic::Rrca => {
+                rrca(&mut self.a, &mut self.f);
+                false
+            }
+            Mnemonic::Rra => {
+                rra(&mut self.a, &mut self.f);
+                false
+            }
+            Mnemonic::Rlc
+            | Mnemonic::Rrc
+            | Mnemonic::Rl
+            | Mnemonic::Rr
+            | Mnemonic::Sla
+            | Mnemonic::Sra
+            | Mnemonic::Swap
+            | Mnemonic::Srl => {
+                let op: fn(&mut u8, &mut Flags) = match instruction.mnemonic {
+                    Mnemonic::Rlc => rlc,
+                    Mnemonic::Rrc => rrc,
+                    Mnemonic::Rl => rl,
+                    Mnemonic::Rr => rr,
+                    Mnemonic::Sla => sla,
+                    Mnemonic::Sra => sra,
+                    Mnemonic::Swap => swap,
+                    _ => srl,
+                };
+                self.modify_operand(first.unwrap(), bus, op);
+                false
+            }
+
+            Mnemonic::Bit | Mnemonic::Res | Mnemonic::Set => {
+                let Some(Operand::Bit(n)) = first else {
+                    unreachable!()
+                };
+                match instruction.mnemonic {
+                    Mnemonic::Bit => {
+                        let val = self.read_operand(second.unwrap(), immediate, bus);
+                        bit(n, val, &mut self.f)
                     }
+                    Mnemonic::Res => self.modify_operand(second.unwrap(), bus, |r, _| res(n, r)),
+                    _ => self.modify_operand(second.unwrap(), bus, |r, _| set(n, r)),
                 }
+                false
             }
 
+            Mnemonic::Jp => {
+                let taken = self.condition_met(instruction);
+                if taken {
+                    self.pc = self.read_pair(instruction.source().unwrap(), immediate);
+                }
+                taken
+            }
+            Mnemonic::Jr => {
+                let taken = self.condition_met(instruction);
+                if taken {
+                    jr(&mut self.pc, immediate as u8);
+                }
+                taken
+            }
+            Mnemonic::Call => {
+                let taken = self.condition_met(instruction);
+                if taken {
+                    call(&mut self.pc, &mut self.sp, immediate, bus);
+                }
+                taken
+            }
+            Mnemonic::Ret => {
+                if instruction.condition().is_some() {
+                    // Checking the condition takes an M-cycle of its own
+                    bus.tick();
+                }
+                let taken = self.condition_met(instruction);
+                if taken {
+                    ret(&mut self.pc, &mut self.sp, bus);
+                }
+                taken
+            }
+            Mnemonic::Reti => {
+                ret(&mut self.pc, &mut self.sp, bus);
+                self.ime = true;
+                false
+            }
+            Mnemonic::Rst => {
+                let Some(Operand::Vector(vector)) = first else {
+                    unreachable!()
+                };
+                call(&mut self.pc, &mut self.sp, vector as u16, bus);
+                false
+            }
+
+            Mnemonic::Halt => {
+                if !self.ime && !bus.pending_interrupts().is_empty() {
+                    // HALT with interrupts disabled but one already pending
+                    // doesn't halt, and trips up the next opcode fetch instead
+                    self.halt_bug = true;
+                } else {
+                    self.halted = true;
+                }
+                false
+            }
+            Mnemonic::Stop => {
+                stop(&mut self.stopped, bus);
+                false
+            }
+            Mnemonic::Di => {
+                di(&mut self.ime, &mut self.ime_scheduled);
+                false
+            }
+            Mnemonic::Ei => {
+                ei(&mut self.ime_scheduled);
+                false
+            }
+            // Undefined opcodes hang the CPU until it's reset
+            Mnemonic::Illegal => {
+                self.locked = true;
+                false
+            }
+        };
+
+        if branched {
+            instruction.branch_cycles
+        } else {
+            instruction.cycles
+        }
+    }
+
+    fn condition_met(&self, instruction: &Instruction) -> bool {
+        match instruction.condition() {
+            None => true,
+            Some(Condition::Nz) => !self.f.contains(Flags::Z),
+            Some(Condition::Z) => self.f.contains(Flags::Z),
+            Some(Condition::Nc) => !self.f.contains(Flags::C),
+            Some(Condition::C) => self.f.contains(Flags::C),
+        }
+    }
+
+    fn read_operand(&mut self, operand: Operand, immediate: u16, bus: &mut impl Bus) -> u8 {
+        match operand {
+            Operand::A => self.a,
+            Operand::B => self.b,
+            Operand::C => self.c,
+            Operand::D => self.d,
+            Operand::E => self.e,
+            Operand::H => self.h,
+            Operand::L => self.l,
+            Operand::Imm8 => immediate as u8,
+            _ => bus.read(self.operand_address(operand, immediate)),
+        }
+    }
+
+    fn write_operand(&mut self, operand: Operand, immediate: u16, val: u8, bus: &mut impl Bus) {
+        match operand {
+            Operand::A => self.a = val,
+            Operand::B => self.b = val,
+            Operand::C => self.c = val,
+            Operand::D => self.d = val,
+            Operand::E => self.e = val,
+            Operand::H => self.h = val,
+            Operand::L => self.l = val,
             _ => {
-                panic!(
-                    "Unimplemented instruction {:x} at {:x}",
-                    instruction, self.pc
-                );
+                let address = self.operand_address(operand, immediate);
+                bus.write(address, val)
+            }
+        }
+    }
+
+    // Read-modify-write of a register or (HL)
+    fn modify_operand(
+        &mut self,
+        operand: Operand,
+        bus: &mut impl Bus,
+        op: impl FnOnce(&mut u8, &mut Flags),
+    ) {
+        let mut val = self.read_operand(operand, 0, bus);
+        op(&mut val, &mut self.f);
+        self.write_operand(operand, 0, val, bus);
+    }
+
+    // The address accessed by a memory operand. (HL+) and (HL-) adjust HL
+    // as a side effect.
+    fn operand_address(&mut self, operand: Operand, immediate: u16) -> u16 {
+        match operand {
+            Operand::BcPtr => self.read_pair(Operand::Bc, immediate),
+            Operand::DePtr => self.read_pair(Operand::De, immediate),
+            Operand::HlPtr => self.read_pair(Operand::Hl, immediate),
+            Operand::HlIncPtr => {
+                let hl = self.read_pair(Operand::Hl, immediate);
+                self.write_pair(Operand::Hl, hl.wrapping_add(1));
+                hl
+            }
+            Operand::HlDecPtr => {
+                let hl = self.read_pair(Operand::Hl, immediate);
+                self.write_pair(Operand::Hl, hl.wrapping_sub(1));
+                hl
             }
+            Operand::CPtr => 0xff00 + self.c as u16,
+            Operand::Imm8Ptr => 0xff00 + (immediate & 0xff),
+            Operand::Imm16Ptr => immediate,
+            _ => unreachable!("{:?} doesn't address memory", operand),
         }
     }
+
+    fn read_pair(&self, operand: Operand, immediate: u16) -> u16 {
+        match operand {
+            Operand::Af => u16::from_be_bytes([self.a, self.f.bits()]),
+            Operand::Bc => u16::from_be_bytes([self.b, self.c]),
+            Operand::De => u16::from_be_bytes([self.d, self.e]),
+            Operand::Hl => u16::from_be_bytes([self.h, self.l]),
+            Operand::Sp => self.sp,
+            Operand::Imm16 => immediate,
+            _ => unreachable!("{:?} isn't a 16-bit operand", operand),
+        }
+    }
+
+    fn write_pair(&mut self, operand: Operand, val: u16) {
+        let [high, low] = val.to_be_bytes();
+        match operand {
+            Operand::Af => {
+                self.a = high;
+                // The low nibble of F is hardwired to zero
+                self.f = Flags::from_bits_retain(low & 0xf0);
+            }
+            Operand::Bc => (self.b, self.c) = (high, low),
+            Operand::De => (self.d, self.e) = (high, low),
+            Operand::Hl => (self.h, self.l) = (high, low),
+            Operand::Sp => self.sp = val,
+            _ => unreachable!("{:?} isn't a 16-bit register", operand),
+        }
+    }
+
+    fn dispatch_interrupt(&mut self, bus: &mut impl Bus) {
+        self.ime = false;
+
+        if self.halt_bug {
+            // EI immediately followed by HALT hits the bug too, but the
+            // interrupt is serviced first and returns to the HALT itself
+            self.halt_bug = false;
+            self.pc = self.pc.wrapping_sub(1);
+        }
+
+        // Two M-cycles pass before PC is pushed
+        bus.tick();
+        bus.tick();
+
+        self.sp = self.sp.wrapping_sub(1);
+        bus.write(self.sp, (self.pc >> 8) as u8);
+
+        // The interrupt to service is only picked once the high byte of PC
+        // has been pushed. If that push overwrote IE and nothing is pending
+        // any more, dispatch is cancelled and execution continues at 0x0000.
+        let vector = match bus.pending_interrupts().highest_priority() {
+            Some((interrupt, vector)) => {
+                bus.reset_interrupt_flag(interrupt);
+                vector
+            }
+            None => 0x0000,
+        };
+
+        self.sp = self.sp.wrapping_sub(1);
+        bus.write(self.sp, (self.pc & 0xff) as u8);
+
+        self.pc = vector;
+        bus.tick();
+    }
+}
+
+// Counts the time spent on the bus during a step
+struct Clocked<'a, B: Bus> {
+    bus: &'a mut B,
+    elapsed: Cycles,
+}
+
+impl<B: Bus> Clocked<'_, B> {
+    fn elapsed(&self) -> Cycles {
+        self.elapsed
+    }
+}
+
+impl<B: Bus> Bus for Clocked<'_, B> {
+    fn read(&mut self, address: u16) -> u8 {
+        self.elapsed += Cycles(4);
+        self.bus.read(address)
+    }
+
+    fn write(&mut self, address: u16, val: u8) {
+        self.elapsed += Cycles(4);
+        self.bus.write(address, val)
+    }
+
+    fn tick(&mut self) {
+        self.elapsed += Cycles(4);
+        self.bus.tick()
+    }
+
+    fn peek(&self, address: u16) -> u8 {
+        self.bus.peek(address)
+    }
+
+    fn pending_interrupts(&self) -> Interrupts {
+        self.bus.pending_interrupts()
+    }
+
+    fn reset_interrupt_flag(&mut self, interrupt: Interrupts) {
+        self.bus.reset_interrupt_flag(interrupt)
+    }
+
+    fn reset_div(&mut self) {
+        self.bus.reset_div()
+    }
 }
 
 impl fmt::Debug for Cpu {
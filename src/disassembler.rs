@@ -0,0 +1,169 @@
+use crate::instruction::{Instruction, Mnemonic, Operand};
+use std::fmt::Write;
+
+pub struct Disassembly {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    pub text: String,
+}
+
+// Decode the instruction at an address. Memory is accessed through `read`, so
+// this works equally on a cartridge, a single ROM bank or the live bus.
+pub fn disassemble(address: u16, read: impl Fn(u16) -> u8) -> Disassembly {
+    let opcode = read(address);
+    let instruction = if opcode == 0xcb {
+        Instruction::decode_cb(read(address.wrapping_add(1)))
+    } else {
+        Instruction::decode(opcode)
+    };
+
+    let bytes: Vec<u8> = (0..instruction.length as u16)
+        .map(|i| read(address.wrapping_add(i)))
+        .collect();
+    let next = address.wrapping_add(instruction.length as u16);
+
+    let text = match instruction.mnemonic {
+        Mnemonic::Illegal => format!("db ${:02x}", opcode),
+        // The byte following STOP is padding
+        Mnemonic::Stop => "stop".to_string(),
+        _ => {
+            let mut text = instruction.mnemonic.to_string();
+            for (i, operand) in instruction.operands().enumerate() {
+                text.push_str(if i == 0 { " " } else { "," });
+                write_operand(&mut text, operand, &bytes, next);
+            }
+            text
+        }
+    };
+
+    Disassembly {
+        address,
+        bytes,
+        text,
+    }
+}
+
+fn write_operand(text: &mut String, operand: Operand, bytes: &[u8], next: u16) {
+    let imm8 = || bytes[bytes.len() - 1];
+    let imm16 = || u16::from_le_bytes([bytes[1], bytes[2]]);
+
+    match operand {
+        Operand::Imm8 => write!(text, "${:02x}", imm8()),
+        Operand::Imm16 => write!(text, "${:04x}", imm16()),
+        Operand::Imm8Ptr => write!(text, "($ff{:02x})", imm8()),
+        Operand::Imm16Ptr => write!(text, "(${:04x})", imm16()),
+        // Relative jumps are shown with their absolute destination
+        Operand::Offset => write!(text, "${:04x}", next.wrapping_add(imm8() as i8 as u16)),
+        Operand::SpOffset => match imm8() as i8 {
+            offset if offset < 0 => write!(text, "sp-${:02x}", offset.unsigned_abs()),
+            offset => write!(text, "sp+${:02x}", offset),
+        },
+        _ => write!(text, "{}", operand),
+    }
+    .unwrap()
+}
+
+// Well known entry points in bank 0
+pub fn label(address: u16) -> Option<&'static str> {
+    match address {
+        0x0000 => Some("rst_00"),
+        0x0008 => Some("rst_08"),
+        0x0010 => Some("rst_10"),
+        0x0018 => Some("rst_18"),
+        0x0020 => Some("rst_20"),
+        0x0028 => Some("rst_28"),
+        0x0030 => Some("rst_30"),
+        0x0038 => Some("rst_38"),
+        0x0040 => Some("vblank"),
+        0x0048 => Some("lcd_stat"),
+        0x0050 => Some("timer"),
+        0x0058 => Some("serial"),
+        0x0060 => Some("joypad"),
+        0x0100 => Some("header"),
+        _ => None,
+    }
+}
+
+// Everything in the header after the entry point is data, which would only
+// disassemble to garbage
+const HEADER_FIELDS: [(u16, u16, &str); 5] = [
+    (0x0104, 0x0134, "logo"),
+    (0x0134, 0x0143, "title"),
+    (0x0143, 0x014d, "cgb flag, licensee, cartridge type, sizes"),
+    (0x014d, 0x014e, "header checksum"),
+    (0x014e, 0x0150, "global checksum"),
+];
+
+// Linear sweep over `from..=to`, one line per instruction
+pub fn listing(from: u16, to: u16, read: impl Fn(u16) -> u8) -> String {
+    let mut out = String::new();
+    let mut address = from as u32;
+
+    while address <= to as u32 {
+        if let Some(label) = label(address as u16) {
+            writeln!(out, "{}:", label).unwrap();
+        }
+
+        let field = HEADER_FIELDS
+            .iter()
+            .find(|(start, end, _)| (*start..*end).contains(&(address as u16)));
+
+        if let Some((_, end, name)) = field {
+            let end = (*end as u32).min(to as u32 + 1);
+            for chunk_start in (address..end).step_by(8) {
+                let bytes: Vec<String> = (chunk_start..end.min(chunk_start + 8))
+                    .map(|a| format!("${:02x}", read(a as u16)))
+                    .collect();
+                writeln!(
+                    out,
+                    "    {:04x}:              db {} ; {}",
+                    chunk_start,
+                    bytes.join(","),
+                    name
+                )
+                .unwrap();
+            }
+            address = end;
+            continue;
+        }
+
+        let disassembly = disassemble(address as u16, &read);
+        let next = address + disassembly.bytes.len() as u32;
+
+        // Whatever runs over a vector can't be code, or the vector would be
+        // unreachable, so it's shown as data up to the label
+        if let Some(vector) = (address + 1..next.min(0x10000)).find(|a| label(*a as u16).is_some())
+        {
+            let bytes: Vec<String> = (address..vector)
+                .map(|a| format!("${:02x}", read(a as u16)))
+                .collect();
+            writeln!(
+                out,
+                "    {:04x}:              db {}",
+                address,
+                bytes.join(",")
+            )
+            .unwrap();
+            address = vector;
+            continue;
+        }
+
+        let bytes: Vec<String> = disassembly
+            .bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        writeln!(
+            out,
+            "    {:04x}: {:<9}    {}",
+            disassembly.address,
+            bytes.join(" "),
+            disassembly.text
+        )
+        .unwrap();
+
+        address = next;
+    }
+
+    out
+}
@@ -1,10 +1,15 @@
 use crate::cartridge::Cartridge;
-use crate::cpu::Cpu;
-use crate::joypad::Joypad;
-use crate::mmu::Mmu;
-use crate::rom_info::RomInfo;
+use crate::cpu::{Cpu, Cycles, Interrupts, Registers, CLOCK_SPEED};
+use crate::joypad::{Button, Joypad};
+use crate::mbc::image_source::ImageSource;
+use crate::mmu::{Mapper, Mmu};
+use crate::rom_info::{HeaderError, RomInfo};
+use crate::save;
 use crate::timers::timers::Timers;
 use crate::video::Video;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
 
 pub struct Gameboy {
     info: RomInfo,
@@ -13,27 +18,55 @@ pub struct Gameboy {
     mmu: Mmu,
     video: Video,
     joypad: Joypad,
+    trace: Option<BufWriter<File>>,
+    // The rumble motor state last reported to the frontend
+    rumbling: bool,
+    save_file: Option<PathBuf>,
+    since_save: Cycles,
 }
 
 impl Gameboy {
-    pub fn new(rom: Vec<u8>) -> Gameboy {
-        let info = RomInfo::new(rom.as_slice());
-        let cartridge = Cartridge::new(rom, info.mbc_type);
+    // Refuses ROMs the boot ROM would lock up on
+    pub fn new(rom: Vec<u8>) -> Result<Gameboy, HeaderError> {
+        RomInfo::new(rom.as_slice())?.validate()?;
+        Gameboy::new_unchecked(rom)
+    }
+
+    // Boots despite a bad header checksum, which homebrew often doesn't bother
+    // fixing up. The mismatch is only printed as a warning.
+    pub fn new_unchecked(rom: Vec<u8>) -> Result<Gameboy, HeaderError> {
+        let info = RomInfo::new(rom.as_slice())?;
+        for warning in info.warnings() {
+            println!("Warning: {}", warning);
+        }
+
+        let cartridge = Cartridge::new(rom, &info);
         let video = Video::new();
         let mmu = Mmu::new(cartridge);
 
         let gb = Gameboy {
             cpu: Cpu::new(info.checksum),
             timers: Timers::new(),
-            info: info,
-            mmu: mmu,
-            video: video,
+            info,
+            mmu,
+            video,
             joypad: Joypad::new(),
+            trace: None,
+            rumbling: false,
+            save_file: None,
+            since_save: Cycles(0),
         };
 
         println!("{}", gb.info.title);
 
-        gb
+        Ok(gb)
+    }
+
+    // Press or release a button. Pressing one also wakes the CPU from STOP.
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        if self.joypad.set(button, pressed) {
+            self.mmu.set_interrupt_flag(Interrupts::JOYPAD);
+        }
     }
 
     pub fn video(&self) -> &Video {
@@ -44,16 +77,134 @@ impl Gameboy {
         self.video.take_frame()
     }
 
-    pub fn step(&mut self) {
-        let cycles = self.cpu.step(
+    // Log the CPU state before every instruction to a file, in the format
+    // used by gameboy-doctor
+    pub fn trace_to(&mut self, path: &Path) -> io::Result<()> {
+        self.trace = Some(BufWriter::new(File::create(path)?));
+        self.video.fix_lcd_y(0x90);
+        Ok(())
+    }
+
+    // How often battery backed RAM is written out while it's being changed,
+    // so that little is lost if the emulator doesn't exit cleanly
+    const SAVE_INTERVAL: Cycles = Cycles(5 * CLOCK_SPEED.0);
+
+    // Keep battery backed RAM in `path`, restoring it from there now
+    pub fn use_save_file(&mut self, path: PathBuf) -> io::Result<()> {
+        save::load(&path, self.mmu.cartridge_mut())?;
+        self.save_file = Some(path);
+        Ok(())
+    }
+
+    // Write out battery backed RAM if it's changed since the last save
+    pub fn flush_save(&mut self) -> io::Result<()> {
+        self.since_save = Cycles(0);
+
+        let Some(path) = &self.save_file else {
+            return Ok(());
+        };
+        // A running clock is always out of date
+        let dirty = self.mmu.cartridge_mut().take_dirty();
+        if !dirty && !self.mmu.cartridge().has_clock() {
+            return Ok(());
+        }
+        save::store(path, self.mmu.cartridge())
+    }
+
+    // Runs a single instruction, returning the time it took
+    pub fn step(&mut self) -> Cycles {
+        // The rest of the system is ticked along with each memory access
+        let mut mapper = Mapper::new(
             &mut self.mmu,
             &mut self.video,
             &mut self.timers,
             &mut self.joypad,
         );
-        self.timers.step(cycles, &mut self.mmu);
-        self.video.step(cycles, &mut self.mmu);
-        // println!("{:?}", self.cpu);
+
+        // A trace that can't be written is given up on rather than
+        // stopping the game
+        if let Some(trace) = &mut self.trace {
+            let written = match self.cpu.trace(&mapper) {
+                Some(line) => writeln!(trace, "{}", line),
+                None => Ok(()),
+            };
+            if let Err(error) = written {
+                println!("Failed to trace: {}", error);
+                self.trace = None;
+            }
+        }
+
+        let cycles = self.cpu.step(&mut mapper);
+
+        self.since_save += cycles;
+        if self.since_save >= Self::SAVE_INTERVAL {
+            if let Err(error) = self.flush_save() {
+                println!("Failed to save: {}", error);
+            }
+        }
+
+        cycles
+    }
+
+    pub fn registers(&self) -> Registers {
+        self.cpu.registers()
+    }
+
+    // Whether an illegal opcode has hung the CPU, which only a reset fixes
+    pub fn locked(&self) -> bool {
+        self.cpu.locked()
+    }
+
+    // Whether a LD B,B breakpoint was hit since the last call
+    pub fn take_breakpoint(&mut self) -> bool {
+        self.cpu.take_breakpoint()
+    }
+
+    // Returns the new state of the cartridge's rumble motor if it was switched
+    // on or off since the last call
+    pub fn take_rumble_event(&mut self) -> Option<bool> {
+        let rumbling = self.mmu.cartridge().rumbling();
+        if rumbling == self.rumbling {
+            return None;
+        }
+        self.rumbling = rumbling;
+        Some(rumbling)
+    }
+
+    // Whether the cartridge's infrared LED is lit
+    pub fn infrared_led(&self) -> bool {
+        self.mmu.cartridge().infrared_led()
+    }
+
+    pub fn receive_infrared(&mut self, light: bool) {
+        self.mmu.cartridge_mut().receive_infrared(light)
+    }
+
+    // Point two cartridges' infrared ports at each other. This only passes
+    // on the current state of each LED, so it needs calling after every step.
+    pub fn link_infrared(&mut self, other: &mut Gameboy) {
+        self.receive_infrared(other.infrared_led());
+        other.receive_infrared(self.infrared_led());
+    }
+
+    // Tilt the Game Boy, for cartridges with an accelerometer. Both axes are
+    // in g, with x increasing when tilted to the right and y when tilted
+    // towards the player. Level is 0, 0.
+    pub fn set_accelerometer(&mut self, x: f32, y: f32) {
+        self.mmu.cartridge_mut().set_accelerometer(x, y)
+    }
+
+    // Where a Game Boy Camera gets its pictures from. Does nothing for other
+    // cartridges.
+    pub fn set_image_source(&mut self, source: Box<dyn ImageSource>) {
+        if let Some(camera) = self.mmu.cartridge_mut().camera_mut() {
+            camera.set_source(source)
+        }
+    }
+
+    // Everything written to the serial port so far
+    pub fn serial_output(&self) -> &[u8] {
+        self.mmu.serial_output()
     }
 
     pub fn rom_info(&self) -> &RomInfo {
@@ -0,0 +1,463 @@
+use crate::cpu::Cycles;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mnemonic {
+    Nop,
+    Ld,
+    Ldh,
+    Push,
+    Pop,
+    Add,
+    Adc,
+    Sub,
+    Sbc,
+    And,
+    Xor,
+    Or,
+    Cp,
+    Inc,
+    Dec,
+    Daa,
+    Cpl,
+    Scf,
+    Ccf,
+    Rlca,
+    Rla,
+    Rrca,
+    Rra,
+    Rlc,
+    Rrc,
+    Rl,
+    Rr,
+    Sla,
+    Sra,
+    Swap,
+    Srl,
+    Bit,
+    Res,
+    Set,
+    Jp,
+    Jr,
+    Call,
+    Ret,
+    Reti,
+    Rst,
+    Halt,
+    Stop,
+    Di,
+    Ei,
+    Prefix,
+    Illegal,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Condition {
+    Nz,
+    Z,
+    Nc,
+    C,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operand {
+    A,
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    Af,
+    Bc,
+    De,
+    Hl,
+    Sp,
+    BcPtr,
+    DePtr,
+    HlPtr,
+    HlIncPtr,
+    HlDecPtr,
+    // (0xff00 + C)
+    CPtr,
+    Imm8,
+    Imm16,
+    // (0xff00 + n8)
+    Imm8Ptr,
+    Imm16Ptr,
+    // Signed 8-bit jump offset
+    Offset,
+    SpOffset,
+    Condition(Condition),
+    Bit(u8),
+    Vector(u8),
+}
+
+impl Operand {
+    pub fn is_register_pair(&self) -> bool {
+        matches!(
+            self,
+            Operand::Af | Operand::Bc | Operand::De | Operand::Hl | Operand::Sp
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Instruction {
+    pub mnemonic: Mnemonic,
+    pub operands: [Option<Operand>; 2],
+    // In bytes, including the opcode and any 0xcb prefix
+    pub length: u8,
+    pub cycles: Cycles,
+    // Cycles taken when a conditional branch is followed
+    pub branch_cycles: Cycles,
+}
+
+impl Instruction {
+    const fn new(
+        mnemonic: Mnemonic,
+        operands: [Option<Operand>; 2],
+        length: u8,
+        cycles: u32,
+    ) -> Self {
+        Self {
+            mnemonic,
+            operands,
+            length,
+            cycles: Cycles(cycles),
+            branch_cycles: Cycles(cycles),
+        }
+    }
+
+    const fn branching(self, branch_cycles: u32) -> Self {
+        Self {
+            branch_cycles: Cycles(branch_cycles),
+            ..self
+        }
+    }
+
+    pub fn decode(opcode: u8) -> &'static Instruction {
+        &INSTRUCTIONS[opcode as usize]
+    }
+
+    pub fn decode_cb(opcode: u8) -> &'static Instruction {
+        &CB_INSTRUCTIONS[opcode as usize]
+    }
+
+    pub fn operands(&self) -> impl Iterator<Item = Operand> + '_ {
+        self.operands.iter().flatten().copied()
+    }
+
+    // The operand read by ALU instructions, where the destination is implied
+    pub fn source(&self) -> Option<Operand> {
+        self.operands[1].or(self.operands[0])
+    }
+
+    pub fn condition(&self) -> Option<Condition> {
+        match self.operands[0] {
+            Some(Operand::Condition(condition)) => Some(condition),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Mnemonic::Nop => "nop",
+            Mnemonic::Ld => "ld",
+            Mnemonic::Ldh => "ldh",
+            Mnemonic::Push => "push",
+            Mnemonic::Pop => "pop",
+            Mnemonic::Add => "add",
+            Mnemonic::Adc => "adc",
+            Mnemonic::Sub => "sub",
+            Mnemonic::Sbc => "sbc",
+            Mnemonic::And => "and",
+            Mnemonic::Xor => "xor",
+            Mnemonic::Or => "or",
+            Mnemonic::Cp => "cp",
+            Mnemonic::Inc => "inc",
+            Mnemonic::Dec => "dec",
+            Mnemonic::Daa => "daa",
+            Mnemonic::Cpl => "cpl",
+            Mnemonic::Scf => "scf",
+            Mnemonic::Ccf => "ccf",
+            Mnemonic::Rlca => "rlca",
+            Mnemonic::Rla => "rla",
+            Mnemonic::Rrca => "rrca",
+            Mnemonic::Rra => "rra",
+            Mnemonic::Rlc => "rlc",
+            Mnemonic::Rrc => "rrc",
+            Mnemonic::Rl => "rl",
+            Mnemonic::Rr => "rr",
+            Mnemonic::Sla => "sla",
+            Mnemonic::Sra => "sra",
+            Mnemonic::Swap => "swap",
+            Mnemonic::Srl => "srl",
+            Mnemonic::Bit => "bit",
+            Mnemonic::Res => "res",
+            Mnemonic::Set => "set",
+            Mnemonic::Jp => "jp",
+            Mnemonic::Jr => "jr",
+            Mnemonic::Call => "call",
+            Mnemonic::Ret => "ret",
+            Mnemonic::Reti => "reti",
+            Mnemonic::Rst => "rst",
+            Mnemonic::Halt => "halt",
+            Mnemonic::Stop => "stop",
+            Mnemonic::Di => "di",
+            Mnemonic::Ei => "ei",
+            Mnemonic::Prefix => "prefix",
+            Mnemonic::Illegal => "illegal",
+        };
+        f.write_str(name)
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Condition::Nz => "nz",
+            Condition::Z => "z",
+            Condition::Nc => "nc",
+            Condition::C => "c",
+        })
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operand::A => f.write_str("a"),
+            Operand::B => f.write_str("b"),
+            Operand::C => f.write_str("c"),
+            Operand::D => f.write_str("d"),
+            Operand::E => f.write_str("e"),
+            Operand::H => f.write_str("h"),
+            Operand::L => f.write_str("l"),
+            Operand::Af => f.write_str("af"),
+            Operand::Bc => f.write_str("bc"),
+            Operand::De => f.write_str("de"),
+            Operand::Hl => f.write_str("hl"),
+            Operand::Sp => f.write_str("sp"),
+            Operand::BcPtr => f.write_str("(bc)"),
+            Operand::DePtr => f.write_str("(de)"),
+            Operand::HlPtr => f.write_str("(hl)"),
+            Operand::HlIncPtr => f.write_str("(hl+)"),
+            Operand::HlDecPtr => f.write_str("(hl-)"),
+            Operand::CPtr => f.write_str("(c)"),
+            Operand::Imm8 => f.write_str("n8"),
+            Operand::Imm16 => f.write_str("n16"),
+            Operand::Imm8Ptr => f.write_str("(a8)"),
+            Operand::Imm16Ptr => f.write_str("(a16)"),
+            Operand::Offset => f.write_str("e8"),
+            Operand::SpOffset => f.write_str("sp+e8"),
+            Operand::Condition(condition) => write!(f, "{}", condition),
+            Operand::Bit(bit) => write!(f, "{}", bit),
+            Operand::Vector(vector) => write!(f, "${:02x}", vector),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.mnemonic)?;
+        for (i, operand) in self.operands().enumerate() {
+            write!(f, "{}{}", if i == 0 { " " } else { "," }, operand)?;
+        }
+        Ok(())
+    }
+}
+
+static INSTRUCTIONS: [Instruction; 256] = table(false);
+static CB_INSTRUCTIONS: [Instruction; 256] = table(true);
+
+const fn table(prefixed: bool) -> [Instruction; 256] {
+    let mut table = [Instruction::new(Mnemonic::Illegal, [None, None], 1, 4); 256];
+    let mut opcode = 0;
+    while opcode < 256 {
+        table[opcode] = if prefixed {
+            decode_cb(opcode as u8)
+        } else {
+            decode(opcode as u8)
+        };
+        opcode += 1;
+    }
+    table
+}
+
+// Register operands as encoded in the low three bits (or bits 3-5) of an
+// opcode, with 0b110 addressing memory through HL
+const R: [Operand; 8] = [
+    Operand::B,
+    Operand::C,
+    Operand::D,
+    Operand::E,
+    Operand::H,
+    Operand::L,
+    Operand::HlPtr,
+    Operand::A,
+];
+const RP: [Operand; 4] = [Operand::Bc, Operand::De, Operand::Hl, Operand::Sp];
+const RP_STACK: [Operand; 4] = [Operand::Bc, Operand::De, Operand::Hl, Operand::Af];
+const CC: [Condition; 4] = [Condition::Nz, Condition::Z, Condition::Nc, Condition::C];
+const ALU: [Mnemonic; 8] = [
+    Mnemonic::Add,
+    Mnemonic::Adc,
+    Mnemonic::Sub,
+    Mnemonic::Sbc,
+    Mnemonic::And,
+    Mnemonic::Xor,
+    Mnemonic::Or,
+    Mnemonic::Cp,
+];
+const ROT: [Mnemonic; 8] = [
+    Mnemonic::Rlc,
+    Mnemonic::Rrc,
+    Mnemonic::Rl,
+    Mnemonic::Rr,
+    Mnemonic::Sla,
+    Mnemonic::Sra,
+    Mnemonic::Swap,
+    Mnemonic::Srl,
+];
+
+const fn decode(opcode: u8) -> Instruction {
+    use Instruction as I;
+    use Mnemonic as M;
+    use Operand as O;
+
+    let y = ((opcode >> 3) & 0b111) as usize;
+    let z = (opcode & 0b111) as usize;
+    let p = y >> 1;
+
+    match opcode {
+        0x00 => I::new(M::Nop, [None, None], 1, 4),
+        0x08 => I::new(M::Ld, [Some(O::Imm16Ptr), Some(O::Sp)], 3, 20),
+        0x10 => I::new(M::Stop, [None, None], 2, 8),
+        0x18 => I::new(M::Jr, [Some(O::Offset), None], 2, 12),
+        0x20 | 0x28 | 0x30 | 0x38 => I::new(
+            M::Jr,
+            [Some(O::Condition(CC[y - 4])), Some(O::Offset)],
+            2,
+            8,
+        )
+        .branching(12),
+        0x01 | 0x11 | 0x21 | 0x31 => I::new(M::Ld, [Some(RP[p]), Some(O::Imm16)], 3, 12),
+        0x09 | 0x19 | 0x29 | 0x39 => I::new(M::Add, [Some(O::Hl), Some(RP[p])], 1, 8),
+        0x02 => I::new(M::Ld, [Some(O::BcPtr), Some(O::A)], 1, 8),
+        0x12 => I::new(M::Ld, [Some(O::DePtr), Some(O::A)], 1, 8),
+        0x22 => I::new(M::Ld, [Some(O::HlIncPtr), Some(O::A)], 1, 8),
+        0x32 => I::new(M::Ld, [Some(O::HlDecPtr), Some(O::A)], 1, 8),
+        0x0a => I::new(M::Ld, [Some(O::A), Some(O::BcPtr)], 1, 8),
+        0x1a => I::new(M::Ld, [Some(O::A), Some(O::DePtr)], 1, 8),
+        0x2a => I::new(M::Ld, [Some(O::A), Some(O::HlIncPtr)], 1, 8),
+        0x3a => I::new(M::Ld, [Some(O::A), Some(O::HlDecPtr)], 1, 8),
+        0x03 | 0x13 | 0x23 | 0x33 => I::new(M::Inc, [Some(RP[p]), None], 1, 8),
+        0x0b | 0x1b | 0x2b | 0x3b => I::new(M::Dec, [Some(RP[p]), None], 1, 8),
+        0x34 => I::new(M::Inc, [Some(O::HlPtr), None], 1, 12),
+        0x35 => I::new(M::Dec, [Some(O::HlPtr), None], 1, 12),
+        0x36 => I::new(M::Ld, [Some(O::HlPtr), Some(O::Imm8)], 2, 12),
+        0x04..=0x3f if z == 4 => I::new(M::Inc, [Some(R[y]), None], 1, 4),
+        0x04..=0x3f if z == 5 => I::new(M::Dec, [Some(R[y]), None], 1, 4),
+        0x04..=0x3f if z == 6 => I::new(M::Ld, [Some(R[y]), Some(O::Imm8)], 2, 8),
+        0x07 => I::new(M::Rlca, [None, None], 1, 4),
+        0x0f => I::new(M::Rrca, [None, None], 1, 4),
+        0x17 => I::new(M::Rla, [None, None], 1, 4),
+        0x1f => I::new(M::Rra, [None, None], 1, 4),
+        0x27 => I::new(M::Daa, [None, None], 1, 4),
+        0x2f => I::new(M::Cpl, [None, None], 1, 4),
+        0x37 => I::new(M::Scf, [None, None], 1, 4),
+        0x3f => I::new(M::Ccf, [None, None], 1, 4),
+
+        0x76 => I::new(M::Halt, [None, None], 1, 4),
+        0x40..=0x7f => {
+            let cycles = if y == 6 || z == 6 { 8 } else { 4 };
+            I::new(M::Ld, [Some(R[y]), Some(R[z])], 1, cycles)
+        }
+
+        0x80..=0xbf => alu(ALU[y], R[z], 1, if z == 6 { 8 } else { 4 }),
+
+        0xc0 | 0xc8 | 0xd0 | 0xd8 => {
+            I::new(M::Ret, [Some(O::Condition(CC[y])), None], 1, 8).branching(20)
+        }
+        0xe0 => I::new(M::Ldh, [Some(O::Imm8Ptr), Some(O::A)], 2, 12),
+        0xe8 => I::new(M::Add, [Some(O::Sp), Some(O::Offset)], 2, 16),
+        0xf0 => I::new(M::Ldh, [Some(O::A), Some(O::Imm8Ptr)], 2, 12),
+        0xf8 => I::new(M::Ld, [Some(O::Hl), Some(O::SpOffset)], 2, 12),
+        0xc1 | 0xd1 | 0xe1 | 0xf1 => I::new(M::Pop, [Some(RP_STACK[p]), None], 1, 12),
+        0xc9 => I::new(M::Ret, [None, None], 1, 16),
+        0xd9 => I::new(M::Reti, [None, None], 1, 16),
+        0xe9 => I::new(M::Jp, [Some(O::Hl), None], 1, 4),
+        0xf9 => I::new(M::Ld, [Some(O::Sp), Some(O::Hl)], 1, 8),
+        0xc2 | 0xca | 0xd2 | 0xda => {
+            I::new(M::Jp, [Some(O::Condition(CC[y])), Some(O::Imm16)], 3, 12).branching(16)
+        }
+        0xe2 => I::new(M::Ldh, [Some(O::CPtr), Some(O::A)], 1, 8),
+        0xea => I::new(M::Ld, [Some(O::Imm16Ptr), Some(O::A)], 3, 16),
+        0xf2 => I::new(M::Ldh, [Some(O::A), Some(O::CPtr)], 1, 8),
+        0xfa => I::new(M::Ld, [Some(O::A), Some(O::Imm16Ptr)], 3, 16),
+        0xc3 => I::new(M::Jp, [Some(O::Imm16), None], 3, 16),
+        0xcb => I::new(M::Prefix, [None, None], 1, 4),
+        0xf3 => I::new(M::Di, [None, None], 1, 4),
+        0xfb => I::new(M::Ei, [None, None], 1, 4),
+        0xc4 | 0xcc | 0xd4 | 0xdc => {
+            I::new(M::Call, [Some(O::Condition(CC[y])), Some(O::Imm16)], 3, 12).branching(24)
+        }
+        0xc5 | 0xd5 | 0xe5 | 0xf5 => I::new(M::Push, [Some(RP_STACK[p]), None], 1, 16),
+        0xcd => I::new(M::Call, [Some(O::Imm16), None], 3, 24),
+        0xc6 | 0xce | 0xd6 | 0xde | 0xe6 | 0xee | 0xf6 | 0xfe => alu(ALU[y], O::Imm8, 2, 8),
+        0xc7 | 0xcf | 0xd7 | 0xdf | 0xe7 | 0xef | 0xf7 | 0xff => {
+            I::new(M::Rst, [Some(O::Vector(opcode & 0x38)), None], 1, 16)
+        }
+
+        // 0xd3, 0xdb, 0xdd, 0xe3, 0xe4, 0xeb, 0xec, 0xed, 0xf4, 0xfc, 0xfd
+        _ => I::new(M::Illegal, [None, None], 1, 4),
+    }
+}
+
+const fn alu(mnemonic: Mnemonic, source: Operand, length: u8, cycles: u32) -> Instruction {
+    // Only the carry-using ops and ADD name A explicitly
+    let operands = match mnemonic {
+        Mnemonic::Add | Mnemonic::Adc | Mnemonic::Sbc => [Some(Operand::A), Some(source)],
+        _ => [Some(source), None],
+    };
+    Instruction::new(mnemonic, operands, length, cycles)
+}
+
+const fn decode_cb(opcode: u8) -> Instruction {
+    use Instruction as I;
+    use Operand as O;
+
+    let y = (opcode >> 3) & 0b111;
+    let z = (opcode & 0b111) as usize;
+    let memory = z == 6;
+
+    match opcode >> 6 {
+        0b00 => I::new(
+            ROT[y as usize],
+            [Some(R[z]), None],
+            2,
+            if memory { 16 } else { 8 },
+        ),
+        0b01 => I::new(
+            Mnemonic::Bit,
+            [Some(O::Bit(y)), Some(R[z])],
+            2,
+            if memory { 12 } else { 8 },
+        ),
+        0b10 => I::new(
+            Mnemonic::Res,
+            [Some(O::Bit(y)), Some(R[z])],
+            2,
+            if memory { 16 } else { 8 },
+        ),
+        _ => I::new(
+            Mnemonic::Set,
+            [Some(O::Bit(y)), Some(R[z])],
+            2,
+            if memory { 16 } else { 8 },
+        ),
+    }
+}
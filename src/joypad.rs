@@ -1,48 +1,86 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Button {
+    Right,
+    Left,
+    Up,
+    Down,
+    A,
+    B,
+    Select,
+    Start,
+}
+
+impl Button {
+    // The line the button pulls low, within its group
+    fn bit(self) -> u8 {
+        match self {
+            Button::Right | Button::A => 0b0001,
+            Button::Left | Button::B => 0b0010,
+            Button::Up | Button::Select => 0b0100,
+            Button::Down | Button::Start => 0b1000,
+        }
+    }
+}
+
 pub struct Joypad {
+    // Bits 4 and 5 of P1. A group is selected when its bit is 0.
     control_bits: u8,
+    // Held buttons, a bit set for each
+    dpad: u8,
+    buttons: u8,
 }
 
-enum Enabled {
-    Buttons,
-    Dpad,
-    Neither,
+impl Default for Joypad {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Joypad {
     pub fn new() -> Self {
-        Self { control_bits: 0 }
+        Self {
+            control_bits: 0,
+            dpad: 0,
+            buttons: 0,
+        }
     }
 
     pub fn read(&self) -> u8 {
-        // 0b11000000
-        //     & self.control_bits << 4
-        //     & match self.enabled() {
-        //         Enabled::Buttons => self.button_bits(),
-        //         Enabled::Dpad => self.dpad_bits(),
-        //         Enabled::Neither => 0b1111,
-        //     }
-        0xef
+        0b11000000 | self.control_bits << 4 | !self.lines() & 0b1111
     }
 
     pub fn write(&mut self, val: u8) {
         self.control_bits = (val >> 4) & 0b11;
     }
 
-    fn enabled(&self) -> Enabled {
-        if self.control_bits & 0b10 != 0 {
-            Enabled::Buttons
-        } else if self.control_bits & 0b1 != 0 {
-            Enabled::Dpad
+    // Returns whether a selected line went low, which requests the joypad
+    // interrupt
+    pub fn set(&mut self, button: Button, pressed: bool) -> bool {
+        let before = self.lines();
+
+        let group = match button {
+            Button::Right | Button::Left | Button::Up | Button::Down => &mut self.dpad,
+            _ => &mut self.buttons,
+        };
+        if pressed {
+            *group |= button.bit();
         } else {
-            Enabled::Neither
+            *group &= !button.bit();
         }
-    }
 
-    fn button_bits(&self) -> u8 {
-        0b1111
+        self.lines() & !before != 0
     }
 
-    fn dpad_bits(&self) -> u8 {
-        0b1111
+    // Which of the four lines are pulled low by held buttons in the
+    // selected groups
+    fn lines(&self) -> u8 {
+        let mut lines = 0;
+        if self.control_bits & 0b01 == 0 {
+            lines |= self.dpad;
+        }
+        if self.control_bits & 0b10 == 0 {
+            lines |= self.buttons;
+        }
+        lines
     }
 }
@@ -0,0 +1,15 @@
+pub mod bus;
+pub mod cartridge;
+pub mod cpu;
+pub mod disassembler;
+pub mod gameboy;
+pub mod instruction;
+pub mod joypad;
+pub mod mbc;
+pub mod mmu;
+mod ops;
+pub mod rom_info;
+pub mod save;
+pub mod test_runner;
+pub mod timers;
+pub mod video;
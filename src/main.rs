@@ -1,33 +1,209 @@
 use pixels::{Pixels, SurfaceTexture};
 use rgb::ComponentBytes;
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
-use winit::event::{Event, WindowEvent};
+use winit::event::{ElementState, Event, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
+use winit::keyboard::{KeyCode, PhysicalKey};
 use winit::window::WindowBuilder;
 
-use crate::video::palette::Palette;
-
-mod cartridge;
-mod cpu;
-mod gameboy;
-mod joypad;
-mod mbc;
-mod mmu;
-mod ops;
-mod rom_info;
-mod timers;
-mod video;
+use missingnogmb::cartridge::Cartridge;
+use missingnogmb::cpu::Cycles;
+use missingnogmb::gameboy::Gameboy;
+use missingnogmb::joypad::Button;
+use missingnogmb::rom_info::{HeaderError, RomInfo};
+use missingnogmb::video::palette::Palette;
+use missingnogmb::{disassembler, test_runner};
 
 fn main() {
     let args: Vec<String> = std::env::args().collect();
-    let filename = &args[1];
-    let path = Path::new(&filename);
-    let mut file = File::open(&path).unwrap();
+
+    match args.get(1).map(String::as_str) {
+        Some("disasm") => disasm(&args[2..]),
+        Some("info") => info(&args[2..]),
+        Some("test") => test(&args[2..]),
+        Some(filename) => run(filename, &args[2..]),
+        None => usage(),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("usage: missingnogmb <rom> [--trace file] [--unchecked]");
+    eprintln!("       missingnogmb info <rom>");
+    eprintln!("       missingnogmb disasm <rom> [--bank N] [--from addr --to addr]");
+    eprintln!("       missingnogmb test <rom> [--cycles N]");
+    std::process::exit(1);
+}
+
+fn load_rom(filename: &str) -> Vec<u8> {
+    let path = Path::new(filename);
+    let mut file = File::open(path).unwrap();
     let mut rom = Vec::new();
     file.read_to_end(&mut rom).unwrap();
-    let mut gb = gameboy::Gameboy::new(rom);
+    rom
+}
+
+// Reject anything that isn't a Game Boy ROM rather than running garbage
+fn check_header<T>(filename: &str, result: Result<T, HeaderError>) -> T {
+    result.unwrap_or_else(|error| {
+        eprintln!("{}: {}", filename, error);
+        std::process::exit(1)
+    })
+}
+
+// Addresses are given in hex, optionally prefixed with 0x or $
+fn parse_address(arg: Option<&String>) -> u16 {
+    let Some(arg) = arg else { usage() };
+    let digits = arg.trim_start_matches("0x").trim_start_matches('$');
+    u16::from_str_radix(digits, 16).unwrap_or_else(|_| {
+        eprintln!("invalid address {}", arg);
+        std::process::exit(1)
+    })
+}
+
+// Print the cartridge header, failing for dumps the boot ROM would refuse
+fn info(args: &[String]) {
+    let [filename] = args else { usage() };
+    let info = check_header(filename, RomInfo::new(&load_rom(filename)));
+    println!("{}", info);
+    for warning in info.warnings() {
+        println!("Warning: {}", warning);
+    }
+    check_header(filename, info.validate());
+}
+
+fn disasm(args: &[String]) {
+    let Some(filename) = args.first() else {
+        usage()
+    };
+    let rom = load_rom(filename);
+
+    let mut bank = None;
+    let mut from = None;
+    let mut to = None;
+    let mut options = args[1..].iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--bank" => match options.next().and_then(|n| n.parse::<usize>().ok()) {
+                Some(n) => bank = Some(n),
+                None => usage(),
+            },
+            "--from" => from = Some(parse_address(options.next())),
+            "--to" => to = Some(parse_address(options.next())),
+            _ => usage(),
+        }
+    }
+
+    let last = (rom.len().clamp(1, 0x8000) - 1) as u16;
+    let listing = match bank {
+        // Show the ROM as the CPU currently sees it
+        None => {
+            let info = check_header(filename, RomInfo::new(&rom));
+            let cartridge = Cartridge::new(rom.clone(), &info);
+            disassembler::listing(from.unwrap_or(0), to.unwrap_or(last).min(last), |address| {
+                cartridge.read(address)
+            })
+        }
+        // Bank 0 is fixed at 0x0000, every other bank is switched in at 0x4000
+        Some(bank) => {
+            let (start, end) = if bank == 0 {
+                (0x0000, 0x3fff)
+            } else {
+                (0x4000, 0x7fff)
+            };
+            let read = |address: u16| {
+                let offset = bank * 0x4000 + (address as usize & 0x3fff);
+                rom.get(offset).copied().unwrap_or(0xff)
+            };
+            disassembler::listing(
+                from.unwrap_or(start).max(start),
+                to.unwrap_or(end).min(end),
+                read,
+            )
+        }
+    };
+
+    print!("{}", listing);
+}
+
+// Run a test ROM headlessly, exiting with a failure status unless it passes
+fn test(args: &[String]) {
+    let Some(filename) = args.first() else {
+        usage()
+    };
+
+    let budget = match &args[1..] {
+        [] => test_runner::DEFAULT_BUDGET,
+        [option, cycles] if option == "--cycles" => match cycles.parse() {
+            Ok(cycles) => Cycles(cycles),
+            Err(_) => usage(),
+        },
+        _ => usage(),
+    };
+
+    let result = check_header(filename, test_runner::run(load_rom(filename), budget));
+    println!("{}", result.serial);
+    println!("{:?} after {} cycles", result.outcome, result.cycles.0);
+
+    if result.outcome != test_runner::Outcome::Passed {
+        std::process::exit(1);
+    }
+}
+
+fn button_for_key(key: KeyCode) -> Option<Button> {
+    match key {
+        KeyCode::ArrowRight => Some(Button::Right),
+        KeyCode::ArrowLeft => Some(Button::Left),
+        KeyCode::ArrowUp => Some(Button::Up),
+        KeyCode::ArrowDown => Some(Button::Down),
+        KeyCode::KeyX => Some(Button::A),
+        KeyCode::KeyZ => Some(Button::B),
+        KeyCode::Backspace => Some(Button::Select),
+        KeyCode::Enter => Some(Button::Start),
+        _ => None,
+    }
+}
+
+// IJKL tilt the Game Boy by 1g for cartridges with an accelerometer, laid
+// out like the arrow keys
+fn tilt_for_key(key: KeyCode) -> Option<(f32, f32)> {
+    match key {
+        KeyCode::KeyL => Some((1.0, 0.0)),
+        KeyCode::KeyJ => Some((-1.0, 0.0)),
+        KeyCode::KeyK => Some((0.0, 1.0)),
+        KeyCode::KeyI => Some((0.0, -1.0)),
+        _ => None,
+    }
+}
+
+fn run(filename: &str, args: &[String]) {
+    let mut trace = None;
+    let mut unchecked = false;
+    let mut args = args.iter();
+    while let Some(option) = args.next() {
+        match option.as_str() {
+            "--trace" => trace = Some(args.next().unwrap_or_else(|| usage())),
+            "--unchecked" => unchecked = true,
+            _ => usage(),
+        }
+    }
+
+    let rom = load_rom(filename);
+    let mut gb = if unchecked {
+        check_header(filename, Gameboy::new_unchecked(rom))
+    } else {
+        check_header(filename, Gameboy::new(rom))
+    };
+
+    // Saves sit next to the ROM, named like other emulators do
+    gb.use_save_file(Path::new(filename).with_extension("sav"))
+        .unwrap();
+
+    if let Some(file) = trace {
+        gb.trace_to(Path::new(file)).unwrap();
+    }
 
     let event_loop = EventLoop::new().unwrap();
     let window = WindowBuilder::new()
@@ -44,6 +220,8 @@ fn main() {
         Pixels::new(width, height, surface_texture).unwrap()
     };
 
+    let mut tilt_keys = HashSet::new();
+
     event_loop.set_control_flow(ControlFlow::Poll);
     event_loop
         .run(move |event, window_target| {
@@ -55,11 +233,37 @@ fn main() {
             }
 
             match event {
+                Event::WindowEvent {
+                    event: WindowEvent::KeyboardInput { event, .. },
+                    ..
+                } => {
+                    if let PhysicalKey::Code(key) = event.physical_key {
+                        let pressed = event.state == ElementState::Pressed;
+                        if let Some(button) = button_for_key(key) {
+                            gb.set_button(button, pressed);
+                        }
+                        if tilt_for_key(key).is_some() {
+                            if pressed {
+                                tilt_keys.insert(key);
+                            } else {
+                                tilt_keys.remove(&key);
+                            }
+                            let (x, y) = tilt_keys
+                                .iter()
+                                .filter_map(|key| tilt_for_key(*key))
+                                .fold((0.0, 0.0), |(x, y), (dx, dy)| (x + dx, y + dy));
+                            gb.set_accelerometer(x, y);
+                        }
+                    }
+                }
                 Event::WindowEvent {
                     event: WindowEvent::CloseRequested,
                     ..
                 } => {
                     println!("The close button was pressed; stopping");
+                    if let Err(error) = gb.flush_save() {
+                        println!("Failed to save: {}", error);
+                    }
                     window_target.exit();
                 }
                 Event::WindowEvent {
@@ -0,0 +1,126 @@
+use crate::cpu::Cycles;
+use crate::mbc::image_source::{ImageSource, TestPattern};
+use crate::mbc::m64282fp::{self, HEIGHT, REGISTERS, WIDTH};
+use crate::mbc::{ram_offset, read_rom, Mbc};
+
+// Captured pictures are left at the start of RAM, after a page the camera
+// doesn't touch
+const PICTURE: usize = 0x100;
+
+// The Game Boy Camera's mapper, which drives an M64282FP image sensor. Its
+// registers replace RAM at 0xa000 when bit 4 of the RAM bank is set.
+pub struct Camera {
+    // RAM can always be read, only writes need enabling
+    ram_write_enabled: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+    registers_mapped: bool,
+    registers: [u8; REGISTERS],
+    // Time left until the capture in progress finishes
+    busy: Cycles,
+    source: Box<dyn ImageSource>,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Camera {
+    pub fn new() -> Camera {
+        Camera {
+            ram_write_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            registers_mapped: false,
+            registers: [0; REGISTERS],
+            busy: Cycles(0),
+            source: Box::new(TestPattern),
+        }
+    }
+
+    pub fn set_source(&mut self, source: Box<dyn ImageSource>) {
+        self.source = source
+    }
+
+    // The picture goes into RAM straight away, but games wait for the busy
+    // flag to clear before reading it
+    fn capture(&mut self, ram: &mut [u8]) -> bool {
+        // Frames of the wrong size are cropped or padded with black, rather
+        // than trusted
+        let mut frame = self.source.frame();
+        frame.resize(WIDTH * HEIGHT, 0);
+        let tiles = m64282fp::process(&self.registers, &frame);
+        self.busy = m64282fp::capture_time(&self.registers);
+        match ram.get_mut(PICTURE..PICTURE + tiles.len()) {
+            Some(picture) => {
+                picture.copy_from_slice(&tiles);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Mbc for Camera {
+    fn read(&self, address: u16, rom: &[u8]) -> u8 {
+        read_rom(rom, self.rom_bank as usize, address)
+    }
+
+    fn write(&mut self, address: u16, val: u8) {
+        match address {
+            0x0000..=0x1fff => self.ram_write_enabled = val & 0x0f == 0x0a,
+            // Bank 0 can be mapped at 0x4000 too
+            0x2000..=0x3fff => self.rom_bank = val & 0x3f,
+            0x4000..=0x5fff => {
+                self.registers_mapped = val & 0x10 != 0;
+                self.ram_bank = val & 0x0f;
+            }
+            0x6000..=0x7fff => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn read_ram(&self, address: u16, ram: &[u8]) -> u8 {
+        if self.registers_mapped {
+            // Only the first register reads back, with bit 0 set while busy.
+            // The registers repeat every 0x80 bytes.
+            return match address & 0x7f {
+                0x00 => self.registers[0] & 0x06 | (self.busy.0 > 0) as u8,
+                _ => 0x00,
+            };
+        }
+        if ram.is_empty() {
+            return 0xff;
+        }
+        ram[ram_offset(ram, self.ram_bank as usize, address)]
+    }
+
+    fn write_ram(&mut self, address: u16, val: u8, ram: &mut [u8]) -> bool {
+        if self.registers_mapped {
+            let register = (address & 0x7f) as usize;
+            if register >= REGISTERS {
+                return false;
+            }
+            self.registers[register] = val;
+            // Pictures are kept in battery backed RAM like anything else
+            return register == 0 && val & 0x01 != 0 && self.busy.0 == 0 && self.capture(ram);
+        }
+        // RAM can't be written while a capture is in progress either
+        if !self.ram_write_enabled || self.busy.0 > 0 || ram.is_empty() {
+            return false;
+        }
+        let offset = ram_offset(ram, self.ram_bank as usize, address);
+        ram[offset] = val;
+        true
+    }
+
+    fn step(&mut self, cycles: Cycles) {
+        self.busy = Cycles(self.busy.0.saturating_sub(cycles.0));
+    }
+
+    fn camera_mut(&mut self) -> Option<&mut Camera> {
+        Some(self)
+    }
+}
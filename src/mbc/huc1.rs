@@ -0,0 +1,78 @@
+use crate::mbc::{ram_offset, read_rom, Mbc};
+
+// Hudson's MBC1 lookalike, which can swap its RAM for an infrared LED and
+// receiver
+pub struct Huc1 {
+    // 0x0e at 0x0000-0x1fff maps the infrared port at 0xa000-0xbfff
+    infrared_mode: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+    led: bool,
+    light: bool,
+}
+
+impl Default for Huc1 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Huc1 {
+    pub fn new() -> Huc1 {
+        Huc1 {
+            infrared_mode: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            led: false,
+            light: false,
+        }
+    }
+}
+
+impl Mbc for Huc1 {
+    fn read(&self, address: u16, rom: &[u8]) -> u8 {
+        read_rom(rom, self.rom_bank as usize, address)
+    }
+
+    fn write(&mut self, address: u16, val: u8) {
+        match address {
+            0x0000..=0x1fff => self.infrared_mode = val & 0x0f == 0x0e,
+            0x2000..=0x3fff => self.rom_bank = (val & 0x3f).max(1),
+            0x4000..=0x5fff => self.ram_bank = val & 0x03,
+            0x6000..=0x7fff => {}
+            _ => unreachable!(),
+        }
+    }
+
+    // RAM is always readable, there's no enable
+    fn read_ram(&self, address: u16, ram: &[u8]) -> u8 {
+        if self.infrared_mode {
+            return 0xc0 | self.light as u8;
+        }
+        if ram.is_empty() {
+            return 0xff;
+        }
+        ram[ram_offset(ram, self.ram_bank as usize, address)]
+    }
+
+    fn write_ram(&mut self, address: u16, val: u8, ram: &mut [u8]) -> bool {
+        if self.infrared_mode {
+            self.led = val & 0x01 != 0;
+            return false;
+        }
+        if ram.is_empty() {
+            return false;
+        }
+        let offset = ram_offset(ram, self.ram_bank as usize, address);
+        ram[offset] = val;
+        true
+    }
+
+    fn infrared_led(&self) -> bool {
+        self.led
+    }
+
+    fn receive_infrared(&mut self, light: bool) {
+        self.light = light
+    }
+}
@@ -0,0 +1,216 @@
+use crate::cpu::{Cycles, CLOCK_SPEED};
+use crate::mbc::{ram_offset, read_rom, Mbc};
+use crate::timers::cycle_timer::CycleTimer;
+
+const MINUTE: Cycles = Cycles(60 * CLOCK_SPEED.0);
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+// Hudson's mapper with a clock and an infrared port. What 0xa000-0xbfff does
+// depends on the mode written to 0x0000-0x1fff.
+pub struct Huc3 {
+    mode: u8,
+    rom_bank: u8,
+    ram_bank: u8,
+    clock: Clock,
+    led: bool,
+    light: bool,
+}
+
+// The clock is a separate chip that's driven by 4 bit commands, and keeps
+// its time in 256 nibbles of memory alongside a running counter
+pub struct Clock {
+    minutes: u16,
+    days: u16,
+    minute_timer: CycleTimer,
+    memory: [u8; 0x100],
+    address: u8,
+    // The last command, and the nibble it left for the game to read
+    command: u8,
+    response: u8,
+}
+
+impl Default for Huc3 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Huc3 {
+    pub fn new() -> Huc3 {
+        Huc3 {
+            mode: 0,
+            rom_bank: 1,
+            ram_bank: 0,
+            clock: Clock::new(),
+            led: false,
+            light: false,
+        }
+    }
+}
+
+impl Mbc for Huc3 {
+    fn read(&self, address: u16, rom: &[u8]) -> u8 {
+        read_rom(rom, self.rom_bank as usize, address)
+    }
+
+    fn write(&mut self, address: u16, val: u8) {
+        match address {
+            0x0000..=0x1fff => self.mode = val & 0x0f,
+            0x2000..=0x3fff => self.rom_bank = val & 0x7f,
+            0x4000..=0x5fff => self.ram_bank = val & 0x03,
+            0x6000..=0x7fff => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn read_ram(&self, address: u16, ram: &[u8]) -> u8 {
+        match self.mode {
+            // RAM is readable in both the read only and read/write modes
+            0x00 | 0x0a if !ram.is_empty() => ram[ram_offset(ram, self.ram_bank as usize, address)],
+            0x0c => 0x80 | self.clock.command << 4 | self.clock.response,
+            // Commands finish straight away, so the clock is always ready
+            0x0d => 0xff,
+            0x0e => 0xc0 | self.light as u8,
+            _ => 0xff,
+        }
+    }
+
+    fn write_ram(&mut self, address: u16, val: u8, ram: &mut [u8]) -> bool {
+        match self.mode {
+            0x0a if !ram.is_empty() => {
+                let offset = ram_offset(ram, self.ram_bank as usize, address);
+                ram[offset] = val;
+                return true;
+            }
+            0x0b => self.clock.command(val),
+            0x0e => self.led = val & 0x01 != 0,
+            _ => {}
+        }
+        false
+    }
+
+    fn step(&mut self, cycles: Cycles) {
+        self.clock.step(cycles)
+    }
+
+    fn huc3_clock(&self) -> Option<&Clock> {
+        Some(&self.clock)
+    }
+
+    fn huc3_clock_mut(&mut self) -> Option<&mut Clock> {
+        Some(&mut self.clock)
+    }
+
+    fn infrared_led(&self) -> bool {
+        self.led
+    }
+
+    fn receive_infrared(&mut self, light: bool) {
+        self.light = light
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock {
+    pub fn new() -> Clock {
+        Clock {
+            minutes: 0,
+            days: 0,
+            minute_timer: CycleTimer::new(MINUTE),
+            memory: [0; 0x100],
+            address: 0,
+            command: 0,
+            response: 0,
+        }
+    }
+
+    pub fn step(&mut self, cycles: Cycles) {
+        self.minute_timer.tick(cycles);
+        if !self.minute_timer.finished() {
+            return;
+        }
+        self.minute_timer.lap();
+        self.add_minutes(1);
+    }
+
+    fn add_minutes(&mut self, minutes: u64) {
+        let total = self.minutes as u64 + minutes;
+        let days = self.days as u64 + total / MINUTES_PER_DAY as u64;
+        self.minutes = (total % MINUTES_PER_DAY as u64) as u16;
+        self.days = (days & 0xfff) as u16;
+    }
+
+    // Move the clock forward by wall clock time spent outside the emulator
+    pub fn advance(&mut self, seconds: u64) {
+        self.add_minutes(seconds / 60);
+        self.step(Cycles((seconds % 60) as u32 * CLOCK_SPEED.0));
+    }
+
+    pub fn minutes(&self) -> u16 {
+        self.minutes
+    }
+
+    pub fn days(&self) -> u16 {
+        self.days
+    }
+
+    pub fn restore(&mut self, minutes: u16, days: u16) {
+        self.minutes = minutes % MINUTES_PER_DAY;
+        self.days = days & 0xfff;
+        self.minute_timer.reset();
+    }
+
+    // The upper nibble picks the command, the lower one is its argument
+    fn command(&mut self, val: u8) {
+        let (command, arg) = ((val >> 4) & 0x07, val & 0x0f);
+        self.command = command;
+
+        match command {
+            // Read or write memory, moving on to the next nibble
+            0x1 => {
+                self.response = self.memory[self.address as usize];
+                self.address = self.address.wrapping_add(1);
+            }
+            0x3 => {
+                self.memory[self.address as usize] = arg;
+                self.address = self.address.wrapping_add(1);
+            }
+            0x4 => self.address = (self.address & 0xf0) | arg,
+            0x5 => self.address = (self.address & 0x0f) | arg << 4,
+            0x6 => match arg {
+                0x0 => self.save_time(),
+                0x1 => self.load_time(),
+                // Status check, which the games expect to read back as 1
+                0x2 => self.response = 0x1,
+                // Anything else drives the speaker, which isn't emulated
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    // Copy the counters into memory, minutes at 0x00-0x02 and days at
+    // 0x03-0x05, low nibble first
+    fn save_time(&mut self) {
+        for i in 0..3 {
+            self.memory[i] = (self.minutes >> (4 * i) & 0x0f) as u8;
+            self.memory[3 + i] = (self.days >> (4 * i) & 0x0f) as u8;
+        }
+    }
+
+    fn load_time(&mut self) {
+        let nibbles = |start: usize| {
+            (0..3).fold(0u16, |val, i| {
+                val | (self.memory[start + i] as u16 & 0x0f) << (4 * i)
+            })
+        };
+        self.minutes = nibbles(0) % MINUTES_PER_DAY;
+        self.days = nibbles(3);
+        self.minute_timer.reset();
+    }
+}
@@ -0,0 +1,130 @@
+use crate::mbc::m64282fp::{HEIGHT, WIDTH};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Where the camera's pictures come from
+pub trait ImageSource {
+    // The next frame, WIDTH x HEIGHT bytes row by row, from black (0) to
+    // white (255)
+    fn frame(&mut self) -> Vec<u8>;
+}
+
+// A diagonal gradient with a black and white square in the middle, so
+// exposure, dithering and edges all show up
+pub struct TestPattern;
+
+impl ImageSource for TestPattern {
+    fn frame(&mut self) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(WIDTH * HEIGHT);
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let in_square = (48..80).contains(&x) && (40..72).contains(&y);
+                let pixel = if in_square {
+                    if (x / 8 + y / 8) % 2 == 0 {
+                        0x00
+                    } else {
+                        0xff
+                    }
+                } else {
+                    ((x + y) * 255 / (WIDTH + HEIGHT - 2)) as u8
+                };
+                frame.push(pixel);
+            }
+        }
+        frame
+    }
+}
+
+// The same picture every time, scaled to fit
+pub struct StaticImage {
+    pixels: Vec<u8>,
+}
+
+impl StaticImage {
+    // Grayscale pixels row by row, of any size
+    pub fn new(pixels: &[u8], width: usize, height: usize) -> io::Result<StaticImage> {
+        if width == 0 || height == 0 || pixels.len() < width * height {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} pixels can't make a {}x{} image",
+                    pixels.len(),
+                    width,
+                    height
+                ),
+            ));
+        }
+
+        let mut scaled = Vec::with_capacity(WIDTH * HEIGHT);
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                scaled.push(pixels[y * height / HEIGHT * width + x * width / WIDTH]);
+            }
+        }
+        Ok(StaticImage { pixels: scaled })
+    }
+
+    // Loads a binary PGM (P5) file, which most image editors can export
+    pub fn open(path: &Path) -> io::Result<StaticImage> {
+        let data = fs::read(path)?;
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        // The header is whitespace separated, with # starting a comment
+        let mut fields = Vec::new();
+        let mut pos = 0;
+        while fields.len() < 4 {
+            while pos < data.len() && data[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if data.get(pos) == Some(&b'#') {
+                while pos < data.len() && data[pos] != b'\n' {
+                    pos += 1;
+                }
+                continue;
+            }
+            let start = pos;
+            while pos < data.len() && !data[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if start == pos {
+                return Err(invalid("truncated PGM header"));
+            }
+            fields.push(String::from_utf8_lossy(&data[start..pos]).into_owned());
+        }
+        // Exactly one whitespace byte separates the header from the pixels
+        pos += 1;
+
+        if fields[0] != "P5" {
+            return Err(invalid("not a binary PGM file"));
+        }
+        let number = |field: &String| {
+            field
+                .parse::<usize>()
+                .map_err(|_| invalid("bad number in PGM header"))
+        };
+        let (width, height, max) = (
+            number(&fields[1])?,
+            number(&fields[2])?,
+            number(&fields[3])?,
+        );
+        if width == 0 || height == 0 || !(1..=255).contains(&max) {
+            return Err(invalid("unsupported PGM dimensions or depth"));
+        }
+
+        let Some(pixels) = data.get(pos..pos + width * height) else {
+            return Err(invalid("truncated PGM data"));
+        };
+        let pixels: Vec<u8> = pixels
+            .iter()
+            .map(|pixel| (*pixel as usize * 255 / max) as u8)
+            .collect();
+        StaticImage::new(&pixels, width, height)
+    }
+}
+
+impl ImageSource for StaticImage {
+    fn frame(&mut self) -> Vec<u8> {
+        self.pixels.clone()
+    }
+}
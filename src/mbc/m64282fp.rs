@@ -0,0 +1,83 @@
+use crate::cpu::Cycles;
+
+// The sensor's picture, as the Game Boy Camera uses it
+pub const WIDTH: usize = 128;
+pub const HEIGHT: usize = 112;
+
+// 6 control registers, then a 4x4 matrix of 3 thresholds each
+pub const REGISTERS: usize = 0x36;
+const MATRIX: usize = 0x06;
+
+// 16x14 tiles of 2bpp data
+pub const TILE_DATA_SIZE: usize = WIDTH * HEIGHT / 4;
+
+// Edge enhancement ratios selected by bits 4-6 of register 4, in quarters
+const EDGE_RATIOS: [i32; 8] = [2, 3, 4, 5, 8, 12, 16, 20];
+
+// How long a capture takes, from the exposure time and the N flag
+pub fn capture_time(registers: &[u8; REGISTERS]) -> Cycles {
+    let exposure = u16::from_be_bytes([registers[2], registers[3]]) as u32;
+    let n = registers[1] & 0x80 != 0;
+    let m_cycles = 32446 + if n { 0 } else { 512 } + 16 * exposure;
+    Cycles(m_cycles * 4)
+}
+
+// Run a frame through the sensor's processing and the mapper's dithering,
+// giving the tiles the camera leaves in RAM
+pub fn process(registers: &[u8; REGISTERS], frame: &[u8]) -> [u8; TILE_DATA_SIZE] {
+    let exposure = u16::from_be_bytes([registers[2], registers[3]]) as i32;
+    // Register 1 bits 5-6 pick which neighbours edges are found from
+    let (horizontal, vertical) = match (registers[1] >> 5) & 0x03 {
+        0 => (false, false),
+        1 => (true, false),
+        2 => (false, true),
+        _ => (true, true),
+    };
+    let ratio = EDGE_RATIOS[((registers[4] >> 4) & 0x07) as usize];
+    let invert = registers[4] & 0x08 != 0;
+
+    // Longer exposures make for a brighter picture, with 0x1000 leaving it
+    // as it was
+    let level = |x: usize, y: usize| {
+        let pixel = frame[y.min(HEIGHT - 1) * WIDTH + x.min(WIDTH - 1)] as i32;
+        let pixel = (pixel * exposure / 0x1000).min(0xff);
+        if invert {
+            0xff - pixel
+        } else {
+            pixel
+        }
+    };
+
+    let mut tiles = [0; TILE_DATA_SIZE];
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let center = level(x, y);
+
+            // Sharpen by how much brighter the pixel is than its neighbours
+            let mut edge = 0;
+            if horizontal {
+                edge += 2 * center - level(x.saturating_sub(1), y) - level(x + 1, y);
+            }
+            if vertical {
+                edge += 2 * center - level(x, y.saturating_sub(1)) - level(x, y + 1);
+            }
+            let color = (center + edge * ratio / 4).clamp(0, 0xff);
+
+            // Each position in the 4x4 matrix has its own thresholds for the
+            // three darker shades
+            let cell = MATRIX + ((y % 4) * 4 + x % 4) * 3;
+            let shade = match registers[cell..cell + 3] {
+                [dark, _, _] if color < dark as i32 => 3,
+                [_, mid, _] if color < mid as i32 => 2,
+                [_, _, light] if color < light as i32 => 1,
+                _ => 0,
+            };
+
+            let row = ((y / 8) * (WIDTH / 8) + x / 8) * 16 + (y % 8) * 2;
+            let bit = 7 - (x % 8);
+            tiles[row] |= (shade & 0x01) << bit;
+            tiles[row + 1] |= (shade >> 1) << bit;
+        }
+    }
+    tiles
+}
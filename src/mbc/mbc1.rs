@@ -0,0 +1,95 @@
+use crate::mbc::{ram_offset, read_rom_bank, Mbc};
+
+pub struct Mbc1 {
+    ram_enabled: bool,
+    // Lower 5 bits of the ROM bank number
+    bank1: u8,
+    // Upper 2 bits of the ROM bank number, or the RAM bank
+    bank2: u8,
+    // In mode 1, bank2 also applies to 0x0000-0x3fff and to RAM
+    advanced_banking: bool,
+    // Multicarts leave bit 4 of bank1 unconnected, and wire bank2 one bit
+    // lower so that each game gets 16 banks
+    multicart: bool,
+}
+
+impl Default for Mbc1 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mbc1 {
+    pub fn new() -> Mbc1 {
+        Mbc1 {
+            ram_enabled: false,
+            bank1: 1,
+            bank2: 0,
+            advanced_banking: false,
+            multicart: false,
+        }
+    }
+
+    pub fn multicart() -> Mbc1 {
+        Mbc1 {
+            multicart: true,
+            ..Mbc1::new()
+        }
+    }
+
+    fn rom_bank(&self, address: u16) -> usize {
+        let (shift, bank1) = if self.multicart {
+            (4, self.bank1 & 0x0f)
+        } else {
+            (5, self.bank1)
+        };
+
+        match address {
+            0x0000..=0x3fff if self.advanced_banking => (self.bank2 as usize) << shift,
+            0x0000..=0x3fff => 0,
+            _ => (self.bank2 as usize) << shift | bank1 as usize,
+        }
+    }
+
+    fn ram_bank(&self) -> usize {
+        if self.advanced_banking {
+            self.bank2 as usize
+        } else {
+            0
+        }
+    }
+}
+
+impl Mbc for Mbc1 {
+    fn read(&self, address: u16, rom: &[u8]) -> u8 {
+        read_rom_bank(rom, self.rom_bank(address), address)
+    }
+
+    fn write(&mut self, address: u16, val: u8) {
+        match address {
+            0x0000..=0x1fff => self.ram_enabled = val & 0x0f == 0x0a,
+            // Bank 0 can't be selected here, so 0x00, 0x20, 0x40 and 0x60
+            // end up selecting the bank after instead
+            0x2000..=0x3fff => self.bank1 = (val & 0x1f).max(1),
+            0x4000..=0x5fff => self.bank2 = val & 0x03,
+            0x6000..=0x7fff => self.advanced_banking = val & 0x01 != 0,
+            _ => unreachable!(),
+        }
+    }
+
+    fn read_ram(&self, address: u16, ram: &[u8]) -> u8 {
+        if !self.ram_enabled || ram.is_empty() {
+            return 0xff;
+        }
+        ram[ram_offset(ram, self.ram_bank(), address)]
+    }
+
+    fn write_ram(&mut self, address: u16, val: u8, ram: &mut [u8]) -> bool {
+        if !self.ram_enabled || ram.is_empty() {
+            return false;
+        }
+        let offset = ram_offset(ram, self.ram_bank(), address);
+        ram[offset] = val;
+        true
+    }
+}
@@ -0,0 +1,55 @@
+use crate::mbc::{read_rom, Mbc};
+
+// MBC2 has 512 half-bytes of RAM built in, stored here one per byte
+pub const RAM_SIZE: usize = 0x200;
+
+pub struct Mbc2 {
+    ram_enabled: bool,
+    rom_bank: u8,
+}
+
+impl Default for Mbc2 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mbc2 {
+    pub fn new() -> Mbc2 {
+        Mbc2 {
+            ram_enabled: false,
+            rom_bank: 1,
+        }
+    }
+}
+
+impl Mbc for Mbc2 {
+    fn read(&self, address: u16, rom: &[u8]) -> u8 {
+        read_rom(rom, self.rom_bank as usize, address)
+    }
+
+    fn write(&mut self, address: u16, val: u8) {
+        match address {
+            // Bit 8 of the address picks the register
+            0x0000..=0x3fff if address & 0x100 == 0 => self.ram_enabled = val & 0x0f == 0x0a,
+            0x0000..=0x3fff => self.rom_bank = (val & 0x0f).max(1),
+            _ => {}
+        }
+    }
+
+    // Only the bottom 9 bits of the address are decoded, so RAM is mirrored
+    // all the way through 0xa000-0xbfff
+    fn read_ram(&self, address: u16, ram: &[u8]) -> u8 {
+        if !self.ram_enabled {
+            return 0xff;
+        }
+        ram[address as usize & 0x1ff] | 0xf0
+    }
+
+    fn write_ram(&mut self, address: u16, val: u8, ram: &mut [u8]) -> bool {
+        if self.ram_enabled {
+            ram[address as usize & 0x1ff] = val & 0x0f
+        }
+        self.ram_enabled
+    }
+}
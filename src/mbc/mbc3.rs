@@ -0,0 +1,95 @@
+use crate::cpu::Cycles;
+use crate::mbc::rtc::Rtc;
+use crate::mbc::{ram_offset, read_rom, Mbc};
+
+pub struct Mbc3 {
+    // Enables both RAM and the clock registers
+    ram_enabled: bool,
+    rom_bank: u8,
+    // RAM banks 0x00-0x03, or clock registers 0x08-0x0c
+    ram_bank: u8,
+    // Latching happens on a write of 0x00 followed by 0x01
+    latch_armed: bool,
+    rtc: Option<Rtc>,
+}
+
+impl Mbc3 {
+    pub fn new(has_rtc: bool) -> Mbc3 {
+        Mbc3 {
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            latch_armed: false,
+            rtc: has_rtc.then(Rtc::new),
+        }
+    }
+}
+
+impl Mbc for Mbc3 {
+    fn read(&self, address: u16, rom: &[u8]) -> u8 {
+        read_rom(rom, self.rom_bank as usize, address)
+    }
+
+    fn write(&mut self, address: u16, val: u8) {
+        match address {
+            0x0000..=0x1fff => self.ram_enabled = val & 0x0f == 0x0a,
+            0x2000..=0x3fff => self.rom_bank = (val & 0x7f).max(1),
+            0x4000..=0x5fff => self.ram_bank = val,
+            0x6000..=0x7fff => {
+                if self.latch_armed && val == 0x01 {
+                    if let Some(rtc) = &mut self.rtc {
+                        rtc.latch();
+                    }
+                }
+                self.latch_armed = val == 0x00;
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn read_ram(&self, address: u16, ram: &[u8]) -> u8 {
+        if !self.ram_enabled {
+            return 0xff;
+        }
+
+        match (self.ram_bank, &self.rtc) {
+            (0x00..=0x03, _) if !ram.is_empty() => {
+                ram[ram_offset(ram, self.ram_bank as usize, address)]
+            }
+            (0x08..=0x0c, Some(rtc)) => rtc.read(self.ram_bank),
+            _ => 0xff,
+        }
+    }
+
+    fn write_ram(&mut self, address: u16, val: u8, ram: &mut [u8]) -> bool {
+        if !self.ram_enabled {
+            return false;
+        }
+
+        match (self.ram_bank, &mut self.rtc) {
+            (0x00..=0x03, _) if !ram.is_empty() => {
+                ram[ram_offset(ram, self.ram_bank as usize, address)] = val;
+                true
+            }
+            (0x08..=0x0c, Some(rtc)) => {
+                rtc.write(self.ram_bank, val);
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn step(&mut self, cycles: Cycles) {
+        if let Some(rtc) = &mut self.rtc {
+            rtc.step(cycles)
+        }
+    }
+
+    fn rtc(&self) -> Option<&Rtc> {
+        self.rtc.as_ref()
+    }
+
+    fn rtc_mut(&mut self) -> Option<&mut Rtc> {
+        self.rtc.as_mut()
+    }
+}
@@ -0,0 +1,65 @@
+use crate::mbc::{ram_offset, read_rom, Mbc};
+
+pub struct Mbc5 {
+    ram_enabled: bool,
+    // 9 bits, and unlike older MBCs bank 0 can be mapped at 0x4000 too
+    rom_bank: u16,
+    ram_bank: u8,
+    // Rumble cartridges use bit 3 of the RAM bank register for the motor
+    has_rumble: bool,
+    rumbling: bool,
+}
+
+impl Mbc5 {
+    pub fn new(has_rumble: bool) -> Mbc5 {
+        Mbc5 {
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            has_rumble,
+            rumbling: false,
+        }
+    }
+}
+
+impl Mbc for Mbc5 {
+    fn read(&self, address: u16, rom: &[u8]) -> u8 {
+        read_rom(rom, self.rom_bank as usize, address)
+    }
+
+    fn write(&mut self, address: u16, val: u8) {
+        match address {
+            // MBC5 checks all 8 bits rather than just the low nibble
+            0x0000..=0x1fff => self.ram_enabled = val == 0x0a,
+            0x2000..=0x2fff => self.rom_bank = (self.rom_bank & 0x100) | val as u16,
+            0x3000..=0x3fff => self.rom_bank = (self.rom_bank & 0xff) | (val as u16 & 0x01) << 8,
+            0x4000..=0x5fff if self.has_rumble => {
+                self.rumbling = val & 0x08 != 0;
+                self.ram_bank = val & 0x07;
+            }
+            0x4000..=0x5fff => self.ram_bank = val & 0x0f,
+            0x6000..=0x7fff => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn read_ram(&self, address: u16, ram: &[u8]) -> u8 {
+        if !self.ram_enabled || ram.is_empty() {
+            return 0xff;
+        }
+        ram[ram_offset(ram, self.ram_bank as usize, address)]
+    }
+
+    fn write_ram(&mut self, address: u16, val: u8, ram: &mut [u8]) -> bool {
+        if !self.ram_enabled || ram.is_empty() {
+            return false;
+        }
+        let offset = ram_offset(ram, self.ram_bank as usize, address);
+        ram[offset] = val;
+        true
+    }
+
+    fn rumbling(&self) -> bool {
+        self.rumbling
+    }
+}
@@ -0,0 +1,303 @@
+use crate::mbc::{read_rom, Mbc};
+
+// The 93LC56 EEPROM holds 128 16-bit words, kept in cartridge RAM with the
+// high byte first so it's saved like any other battery backed RAM
+pub const EEPROM_SIZE: usize = 0x100;
+
+// What the accelerometer reads when level, and how far 1g moves it
+const ACCELEROMETER_CENTER: u16 = 0x81d0;
+const ACCELEROMETER_G: f32 = 112.0;
+
+// The MBC7 has no RAM, only an accelerometer and a serial EEPROM behind
+// registers at 0xa000-0xafff. Both RAM enables have to be set to reach them.
+pub struct Mbc7 {
+    ram_enabled: bool,
+    ram_enabled2: bool,
+    rom_bank: u8,
+    // The current reading, and the copy the game sees after latching it
+    accelerometer: (u16, u16),
+    latched: (u16, u16),
+    // Writing 0x55 then 0xaa latches the accelerometer, and only once
+    latch_ready: bool,
+    eeprom: Eeprom,
+}
+
+impl Default for Mbc7 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mbc7 {
+    pub fn new() -> Mbc7 {
+        let center = (ACCELEROMETER_CENTER, ACCELEROMETER_CENTER);
+        Mbc7 {
+            ram_enabled: false,
+            ram_enabled2: false,
+            rom_bank: 1,
+            accelerometer: center,
+            latched: (0x8000, 0x8000),
+            latch_ready: false,
+            eeprom: Eeprom::new(),
+        }
+    }
+
+    fn registers_enabled(&self, address: u16) -> bool {
+        self.ram_enabled && self.ram_enabled2 && address < 0xb000
+    }
+}
+
+impl Mbc for Mbc7 {
+    fn read(&self, address: u16, rom: &[u8]) -> u8 {
+        read_rom(rom, self.rom_bank as usize, address)
+    }
+
+    fn write(&mut self, address: u16, val: u8) {
+        match address {
+            0x0000..=0x1fff => self.ram_enabled = val & 0x0f == 0x0a,
+            0x2000..=0x3fff => self.rom_bank = val & 0x7f,
+            0x4000..=0x5fff => self.ram_enabled2 = val == 0x40,
+            0x6000..=0x7fff => {}
+            _ => unreachable!(),
+        }
+    }
+
+    // Registers are picked by bits 4-7 of the address
+    fn read_ram(&self, address: u16, _ram: &[u8]) -> u8 {
+        if !self.registers_enabled(address) {
+            return 0xff;
+        }
+        let (x, y) = self.latched;
+        match (address >> 4) & 0x0f {
+            0x2 => x as u8,
+            0x3 => (x >> 8) as u8,
+            0x4 => y as u8,
+            0x5 => (y >> 8) as u8,
+            0x6 => 0x00,
+            0x8 => self.eeprom.read(),
+            _ => 0xff,
+        }
+    }
+
+    fn write_ram(&mut self, address: u16, val: u8, ram: &mut [u8]) -> bool {
+        if !self.registers_enabled(address) {
+            return false;
+        }
+        match (address >> 4) & 0x0f {
+            0x0 if val == 0x55 => {
+                self.latched = (0x8000, 0x8000);
+                self.latch_ready = true;
+            }
+            0x1 if val == 0xaa && self.latch_ready => {
+                self.latched = self.accelerometer;
+                self.latch_ready = false;
+            }
+            0x8 => return self.eeprom.write(val, ram),
+            _ => {}
+        }
+        false
+    }
+
+    fn set_accelerometer(&mut self, x: f32, y: f32) {
+        let reading = |g: f32| (ACCELEROMETER_CENTER as f32 + g * ACCELEROMETER_G) as u16;
+        self.accelerometer = (reading(x), reading(y));
+    }
+}
+
+enum EepromState {
+    // Waiting for a start bit
+    Idle,
+    // Shifting in the opcode and address
+    Command {
+        bits: u16,
+        count: u8,
+    },
+    // Shifting out words from `address` on
+    Reading {
+        address: u8,
+        data: u16,
+        count: u8,
+    },
+    // Shifting in a word to write to `address`, or everywhere if None
+    Writing {
+        address: Option<u8>,
+        data: u16,
+        count: u8,
+    },
+}
+
+// The 93LC56 in its 16-bit mode, driven a bit at a time through register
+// 0x8: chip select in bit 7, clock in bit 6, data in through bit 1 and out
+// through bit 0
+struct Eeprom {
+    chip_select: bool,
+    clock: bool,
+    data_out: bool,
+    write_enabled: bool,
+    state: EepromState,
+    // Set when a command changes the contents
+    programmed: bool,
+}
+
+impl Eeprom {
+    fn new() -> Eeprom {
+        Eeprom {
+            chip_select: false,
+            clock: false,
+            data_out: true,
+            write_enabled: false,
+            state: EepromState::Idle,
+            programmed: false,
+        }
+    }
+
+    fn read(&self) -> u8 {
+        (self.chip_select as u8) << 7 | (self.clock as u8) << 6 | self.data_out as u8
+    }
+
+    // Returns whether a command finished changing the contents
+    fn write(&mut self, val: u8, ram: &mut [u8]) -> bool {
+        let chip_select = val & 0x80 != 0;
+        let clock = val & 0x40 != 0;
+        let data_in = (val >> 1) & 0x01;
+
+        // Dropping chip select abandons whatever was going on
+        if !chip_select {
+            self.state = EepromState::Idle;
+        } else if clock && !self.clock {
+            self.clock_in(data_in, ram);
+        }
+        self.chip_select = chip_select;
+        self.clock = clock;
+        std::mem::take(&mut self.programmed)
+    }
+
+    // Everything happens on the rising edge of the clock
+    fn clock_in(&mut self, data_in: u8, ram: &mut [u8]) {
+        self.state = match std::mem::replace(&mut self.state, EepromState::Idle) {
+            EepromState::Idle if data_in == 1 => EepromState::Command { bits: 0, count: 0 },
+            EepromState::Idle => EepromState::Idle,
+            EepromState::Command { bits, count } => {
+                let bits = bits << 1 | data_in as u16;
+                if count + 1 < 10 {
+                    EepromState::Command {
+                        bits,
+                        count: count + 1,
+                    }
+                } else {
+                    self.command(bits, ram)
+                }
+            }
+            EepromState::Reading {
+                address,
+                data,
+                count,
+            } => {
+                self.data_out = data & 0x8000 != 0;
+                // Reads carry on into the next word for as long as the clock
+                // keeps going
+                if count + 1 < 16 {
+                    EepromState::Reading {
+                        address,
+                        data: data << 1,
+                        count: count + 1,
+                    }
+                } else {
+                    let address = (address + 1) & 0x7f;
+                    EepromState::Reading {
+                        address,
+                        data: read_word(ram, address),
+                        count: 0,
+                    }
+                }
+            }
+            EepromState::Writing {
+                address,
+                data,
+                count,
+            } => {
+                let data = data << 1 | data_in as u16;
+                if count + 1 < 16 {
+                    EepromState::Writing {
+                        address,
+                        data,
+                        count: count + 1,
+                    }
+                } else {
+                    if self.write_enabled {
+                        match address {
+                            Some(address) => write_word(ram, address, data),
+                            None => (0..0x80).for_each(|address| write_word(ram, address, data)),
+                        }
+                        self.programmed = true;
+                    }
+                    self.data_out = true;
+                    EepromState::Idle
+                }
+            }
+        }
+    }
+
+    // Two bits of opcode and eight of address, of which only the low 7 are
+    // used. Opcode 0 uses the top of the address to pick a further command.
+    fn command(&mut self, bits: u16, ram: &mut [u8]) -> EepromState {
+        let address = (bits & 0x7f) as u8;
+        match (bits >> 8, (bits >> 6) & 0x03) {
+            // READ, which starts with a dummy 0 bit
+            (0b10, _) => {
+                self.data_out = false;
+                return EepromState::Reading {
+                    address,
+                    data: read_word(ram, address),
+                    count: 0,
+                };
+            }
+            // WRITE and WRAL
+            (0b01, _) => {
+                return EepromState::Writing {
+                    address: Some(address),
+                    data: 0,
+                    count: 0,
+                }
+            }
+            (0b00, 0b01) => {
+                return EepromState::Writing {
+                    address: None,
+                    data: 0,
+                    count: 0,
+                }
+            }
+            // ERASE and ERAL
+            (0b11, _) if self.write_enabled => {
+                write_word(ram, address, 0xffff);
+                self.programmed = true;
+            }
+            (0b00, 0b10) if self.write_enabled => {
+                (0..0x80).for_each(|address| write_word(ram, address, 0xffff));
+                self.programmed = true;
+            }
+            // EWEN and EWDS
+            (0b00, 0b11) => self.write_enabled = true,
+            (0b00, 0b00) => self.write_enabled = false,
+            _ => {}
+        }
+        // Programming is instant, so the chip is always ready
+        self.data_out = true;
+        EepromState::Idle
+    }
+}
+
+fn read_word(ram: &[u8], address: u8) -> u16 {
+    let offset = address as usize * 2;
+    match ram.get(offset..offset + 2) {
+        Some(word) => u16::from_be_bytes([word[0], word[1]]),
+        None => 0xffff,
+    }
+}
+
+fn write_word(ram: &mut [u8], address: u8, val: u16) {
+    let offset = address as usize * 2;
+    if let Some(word) = ram.get_mut(offset..offset + 2) {
+        word.copy_from_slice(&val.to_be_bytes());
+    }
+}
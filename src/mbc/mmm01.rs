@@ -0,0 +1,164 @@
+use crate::mbc::{ram_offset, read_rom_bank, Mbc};
+
+// The MMM01 starts out unmapped, with the menu in the last 32KiB of the ROM.
+// The menu sets up which part of the ROM and RAM a game gets, then maps it in,
+// after which the cartridge behaves as an MBC1 limited to that game.
+pub struct Mmm01 {
+    mapped: bool,
+    ram_enabled: bool,
+    // ROM bank bits 0-4, 5-6 and 7-8
+    rom_bank_low: u8,
+    rom_bank_mid: u8,
+    rom_bank_high: u8,
+    // RAM bank bits 0-1 and 2-3
+    ram_bank_low: u8,
+    ram_bank_high: u8,
+    // Set bits of rom_bank_low (from bit 1 up) and ram_bank_low that the game
+    // can't change once mapped
+    rom_bank_mask: u8,
+    ram_bank_mask: u8,
+    mbc1_mode: bool,
+    mbc1_mode_locked: bool,
+    // Games whose MBC1 bank2 register switches ROM bits 5-6, with the RAM bank
+    // fixed by the menu instead
+    multiplex: bool,
+}
+
+impl Default for Mmm01 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mmm01 {
+    pub fn new() -> Mmm01 {
+        Mmm01 {
+            mapped: false,
+            ram_enabled: false,
+            rom_bank_low: 0,
+            rom_bank_mid: 0,
+            rom_bank_high: 0,
+            ram_bank_low: 0,
+            ram_bank_high: 0,
+            rom_bank_mask: 0,
+            ram_bank_mask: 0,
+            mbc1_mode: false,
+            mbc1_mode_locked: false,
+            multiplex: false,
+        }
+    }
+
+    fn rom_bank(&self, address: u16, rom: &[u8]) -> usize {
+        if !self.mapped {
+            let last = rom.len() / 0x4000;
+            return match address {
+                0x0000..=0x3fff => last.saturating_sub(2),
+                _ => last.saturating_sub(1),
+            };
+        }
+
+        let mid = if self.multiplex {
+            self.ram_bank_low
+        } else {
+            self.rom_bank_mid
+        };
+        let high = (self.rom_bank_high as usize) << 7;
+
+        // Like MBC1, only the locked bits of the low bank apply to
+        // 0x0000-0x3fff, and the upper ones only in mode 1 when multiplexed
+        let rom0 = (self.rom_bank_low & self.rom_bank_mask << 1) as usize
+            | if !self.multiplex || self.mbc1_mode {
+                (mid as usize) << 5
+            } else {
+                0
+            }
+            | high;
+        match address {
+            0x0000..=0x3fff => rom0,
+            _ => {
+                let bank = self.rom_bank_low as usize | (mid as usize) << 5 | high;
+                // Bank 0 of the game can't be selected here either
+                if bank == rom0 {
+                    bank + 1
+                } else {
+                    bank
+                }
+            }
+        }
+    }
+
+    fn ram_bank(&self) -> usize {
+        let low = if self.multiplex {
+            self.rom_bank_mid
+        } else {
+            self.ram_bank_low
+        };
+        (self.ram_bank_high << 2 | low) as usize
+    }
+}
+
+impl Mbc for Mmm01 {
+    fn read(&self, address: u16, rom: &[u8]) -> u8 {
+        read_rom_bank(rom, self.rom_bank(address, rom), address)
+    }
+
+    fn write(&mut self, address: u16, val: u8) {
+        // Writes from the game can't touch the bits the menu locked
+        let rom_mask = if self.mapped {
+            self.rom_bank_mask << 1
+        } else {
+            0
+        };
+        let ram_mask = if self.mapped { self.ram_bank_mask } else { 0 };
+
+        match address {
+            0x0000..=0x1fff => {
+                self.ram_enabled = val & 0x0f == 0x0a;
+                if !self.mapped {
+                    self.ram_bank_mask = (val >> 4) & 0x03;
+                    self.mapped = val & 0x40 != 0;
+                }
+            }
+            0x2000..=0x3fff => {
+                self.rom_bank_low = (self.rom_bank_low & rom_mask) | (val & 0x1f & !rom_mask);
+                if !self.mapped {
+                    self.rom_bank_mid = (val >> 5) & 0x03;
+                }
+            }
+            0x4000..=0x5fff => {
+                self.ram_bank_low = (self.ram_bank_low & ram_mask) | (val & 0x03 & !ram_mask);
+                if !self.mapped {
+                    self.ram_bank_high = (val >> 2) & 0x03;
+                    self.rom_bank_high = (val >> 4) & 0x03;
+                    self.mbc1_mode_locked = val & 0x40 != 0;
+                }
+            }
+            0x6000..=0x7fff => {
+                if !self.mbc1_mode_locked {
+                    self.mbc1_mode = val & 0x01 != 0;
+                }
+                if !self.mapped {
+                    self.rom_bank_mask = (val >> 2) & 0x0f;
+                    self.multiplex = val & 0x40 != 0;
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn read_ram(&self, address: u16, ram: &[u8]) -> u8 {
+        if !self.ram_enabled || ram.is_empty() {
+            return 0xff;
+        }
+        ram[ram_offset(ram, self.ram_bank(), address)]
+    }
+
+    fn write_ram(&mut self, address: u16, val: u8, ram: &mut [u8]) -> bool {
+        if !self.ram_enabled || ram.is_empty() {
+            return false;
+        }
+        let offset = ram_offset(ram, self.ram_bank(), address);
+        ram[offset] = val;
+        true
+    }
+}
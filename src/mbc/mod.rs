@@ -1,6 +1,89 @@
+pub mod camera;
+pub mod huc1;
+pub mod huc3;
+pub mod image_source;
+pub mod m64282fp;
+pub mod mbc1;
+pub mod mbc2;
+pub mod mbc3;
+pub mod mbc5;
+pub mod mbc7;
+pub mod mmm01;
 pub mod no_mbc;
+pub mod rtc;
+
+use crate::cpu::Cycles;
+use camera::Camera;
+use huc3::Clock;
+use rtc::Rtc;
 
 pub trait Mbc {
     fn read(&self, address: u16, rom: &[u8]) -> u8;
     fn write(&mut self, address: u16, val: u8);
+
+    // External RAM at 0xa000-0xbfff
+    fn read_ram(&self, address: u16, ram: &[u8]) -> u8;
+    // Returns whether RAM was changed, rather than a register behind it
+    fn write_ram(&mut self, address: u16, val: u8, ram: &mut [u8]) -> bool;
+
+    // Advance anything on the cartridge that keeps time
+    fn step(&mut self, _cycles: Cycles) {}
+
+    fn rtc(&self) -> Option<&Rtc> {
+        None
+    }
+
+    fn rtc_mut(&mut self) -> Option<&mut Rtc> {
+        None
+    }
+
+    // HuC3 cartridges keep time in their own way
+    fn huc3_clock(&self) -> Option<&Clock> {
+        None
+    }
+
+    fn huc3_clock_mut(&mut self) -> Option<&mut Clock> {
+        None
+    }
+
+    // Whether a rumble motor on the cartridge is switched on
+    fn rumbling(&self) -> bool {
+        false
+    }
+
+    // Whether an infrared LED on the cartridge is lit
+    fn infrared_led(&self) -> bool {
+        false
+    }
+
+    // Tell the cartridge's infrared receiver whether it's seeing light
+    fn receive_infrared(&mut self, _light: bool) {}
+
+    // Tilt in g for cartridges with an accelerometer
+    fn set_accelerometer(&mut self, _x: f32, _y: f32) {}
+
+    fn camera_mut(&mut self) -> Option<&mut Camera> {
+        None
+    }
+}
+
+// Read from one of the ROM's 16KiB banks. Bank numbers beyond the size of the
+// ROM wrap around.
+pub fn read_rom_bank(rom: &[u8], bank: usize, address: u16) -> u8 {
+    rom[(bank * 0x4000 + (address as usize & 0x3fff)) % rom.len()]
+}
+
+// The usual layout, with bank 0 fixed at 0x0000-0x3fff and `bank` switched in
+// at 0x4000-0x7fff
+pub fn read_rom(rom: &[u8], bank: usize, address: u16) -> u8 {
+    match address {
+        0x0000..=0x3fff => read_rom_bank(rom, 0, address),
+        _ => read_rom_bank(rom, bank, address),
+    }
+}
+
+// Where an address in 0xa000-0xbfff lands in RAM with one of its 8KiB banks
+// mapped, wrapping around like ROM banks do
+pub fn ram_offset(ram: &[u8], bank: usize, address: u16) -> usize {
+    (bank * 0x2000 + (address as usize - 0xa000)) % ram.len()
 }
@@ -2,6 +2,12 @@ use crate::mbc::Mbc;
 
 pub struct NoMbc;
 
+impl Default for NoMbc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NoMbc {
     pub fn new() -> NoMbc {
         NoMbc
@@ -10,10 +16,26 @@ impl NoMbc {
 
 impl Mbc for NoMbc {
     fn read(&self, address: u16, rom: &[u8]) -> u8 {
-        rom[address as usize]
+        // Truncated dumps read as open bus past their end
+        rom.get(address as usize).copied().unwrap_or(0xff)
     }
 
     fn write(&mut self, _address: u16, _val: u8) {
         // No MBC, so noop!
     }
+
+    // A few cartridges have up to 8KiB of RAM wired up directly
+    fn read_ram(&self, address: u16, ram: &[u8]) -> u8 {
+        ram.get(address as usize - 0xa000).copied().unwrap_or(0xff)
+    }
+
+    fn write_ram(&mut self, address: u16, val: u8, ram: &mut [u8]) -> bool {
+        match ram.get_mut(address as usize - 0xa000) {
+            Some(byte) => {
+                *byte = val;
+                true
+            }
+            None => false,
+        }
+    }
 }
@@ -0,0 +1,191 @@
+use crate::cpu::{Cycles, CLOCK_SPEED};
+use crate::timers::cycle_timer::CycleTimer;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// The MBC3 real time clock. Registers are numbered as they're selected
+// through 0x4000-0x5fff, from 0x08 (seconds) to 0x0c (upper day bits and
+// flags).
+pub struct Rtc {
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    days: u16,
+    halted: bool,
+    // Set when the day counter overflows, until cleared by the game
+    carry: bool,
+
+    // What the game reads back, as of the last latch
+    latched: [u8; 5],
+    second_timer: CycleTimer,
+}
+
+impl Default for Rtc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rtc {
+    pub fn new() -> Rtc {
+        Rtc {
+            seconds: 0,
+            minutes: 0,
+            hours: 0,
+            days: 0,
+            halted: false,
+            carry: false,
+            latched: [0; 5],
+            second_timer: CycleTimer::new(CLOCK_SPEED),
+        }
+    }
+
+    pub fn step(&mut self, cycles: Cycles) {
+        if self.halted {
+            return;
+        }
+
+        self.second_timer.tick(cycles);
+        if self.second_timer.finished() {
+            self.second_timer.lap();
+            self.tick();
+        }
+    }
+
+    // Move the clock forward by wall clock time spent outside the emulator
+    pub fn advance(&mut self, mut seconds: u64) {
+        if self.halted {
+            return;
+        }
+
+        // Out of range values have to count up to their wraparound one
+        // second at a time, after which the rest can be done at once
+        while seconds > 0 && !self.in_range() {
+            self.tick();
+            seconds -= 1;
+        }
+
+        let total = self.seconds as u64
+            + self.minutes as u64 * 60
+            + self.hours as u64 * 3600
+            + self.days as u64 * 86400
+            + seconds;
+        self.seconds = (total % 60) as u8;
+        self.minutes = (total / 60 % 60) as u8;
+        self.hours = (total / 3600 % 24) as u8;
+
+        let days = total / 86400;
+        if days > 0x1ff {
+            self.carry = true;
+        }
+        self.days = (days & 0x1ff) as u16;
+    }
+
+    fn in_range(&self) -> bool {
+        self.seconds < 60 && self.minutes < 60 && self.hours < 24
+    }
+
+    // The counters only carry when reaching their proper limit. Values
+    // written out of range wrap around at the register width without
+    // carrying.
+    fn tick(&mut self) {
+        self.seconds = (self.seconds + 1) & 0x3f;
+        if self.seconds != 60 {
+            return;
+        }
+        self.seconds = 0;
+
+        self.minutes = (self.minutes + 1) & 0x3f;
+        if self.minutes != 60 {
+            return;
+        }
+        self.minutes = 0;
+
+        self.hours = (self.hours + 1) & 0x1f;
+        if self.hours != 24 {
+            return;
+        }
+        self.hours = 0;
+
+        self.days += 1;
+        if self.days > 0x1ff {
+            self.days = 0;
+            self.carry = true;
+        }
+    }
+
+    pub fn latch(&mut self) {
+        self.latched = self.registers();
+    }
+
+    pub fn read(&self, register: u8) -> u8 {
+        let [seconds, minutes, hours, days_low, days_high] = self.latched;
+        match register {
+            0x08 => seconds | 0xc0,
+            0x09 => minutes | 0xc0,
+            0x0a => hours | 0xe0,
+            0x0b => days_low,
+            0x0c => days_high | 0x3e,
+            _ => 0xff,
+        }
+    }
+
+    pub fn write(&mut self, register: u8, val: u8) {
+        match register {
+            0x08 => {
+                // Writing the seconds restarts the current second
+                self.seconds = val & 0x3f;
+                self.second_timer.reset();
+            }
+            0x09 => self.minutes = val & 0x3f,
+            0x0a => self.hours = val & 0x1f,
+            0x0b => self.days = (self.days & 0x100) | val as u16,
+            0x0c => {
+                self.days = (self.days & 0xff) | ((val as u16 & 0x01) << 8);
+                self.halted = val & 0x40 != 0;
+                self.carry = val & 0x80 != 0;
+            }
+            _ => {}
+        }
+    }
+
+    // The live counters, in register order
+    pub fn registers(&self) -> [u8; 5] {
+        [
+            self.seconds,
+            self.minutes,
+            self.hours,
+            (self.days & 0xff) as u8,
+            (self.days >> 8) as u8 | (self.halted as u8) << 6 | (self.carry as u8) << 7,
+        ]
+    }
+
+    pub fn latched_registers(&self) -> [u8; 5] {
+        self.latched
+    }
+
+    pub fn restore(&mut self, registers: [u8; 5], latched: [u8; 5]) {
+        for (register, val) in (0x08..).zip(registers) {
+            self.write(register, val);
+        }
+        self.latched = latched;
+    }
+}
+
+pub fn to_unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+pub fn from_unix_time(seconds: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(seconds)
+}
+
+// Wall clock time since `saved_at`, for clocks to catch up on as if they kept
+// running while the emulator was closed
+pub fn seconds_since(saved_at: SystemTime) -> u64 {
+    SystemTime::now()
+        .duration_since(saved_at)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
@@ -1,5 +1,6 @@
+use crate::bus::Bus;
 use crate::cartridge::Cartridge;
-use crate::cpu::Interrupts;
+use crate::cpu::{Cycles, Interrupts};
 use crate::joypad::Joypad;
 use crate::timers::timers::Timers;
 use crate::video::Video;
@@ -13,6 +14,8 @@ pub struct Mmu {
     serial_debug: Vec<u8>,
 }
 
+// The system as seen from the CPU. Every memory access takes one M-cycle,
+// during which the rest of the system is ticked along with it.
 pub struct Mapper<'a> {
     mmu: &'a mut Mmu,
     video: &'a mut Video,
@@ -21,6 +24,8 @@ pub struct Mapper<'a> {
 }
 
 impl Mapper<'_> {
+    const M_CYCLE: Cycles = Cycles(4);
+
     pub fn new<'a>(
         mmu: &'a mut Mmu,
         video: &'a mut Video,
@@ -34,43 +39,59 @@ impl Mapper<'_> {
             joypad,
         }
     }
+}
 
-    pub fn read(&self, address: u16) -> u8 {
-        self.mmu.read(address, self.video, self.timers, self.joypad)
-    }
+impl Bus for Mapper<'_> {
+    fn read(&mut self, address: u16) -> u8 {
+        self.tick();
 
-    pub fn read_pc(&self, pc: &mut u16) -> u8 {
-        let val = self.read(*pc);
-        *pc += 1;
-        val
+        // Only HRAM is reachable while OAM DMA has the bus
+        if self.video.dma_transfer_in_progess() && !(0xff80..0xffff).contains(&address) {
+            return 0xff;
+        }
+
+        self.peek(address)
     }
 
-    pub fn read_word(&self, address: u16) -> u16 {
+    fn write(&mut self, address: u16, val: u8) {
+        self.tick();
         self.mmu
-            .read_word(address, self.video, self.timers, self.joypad)
+            .write(address, val, self.video, self.timers, self.joypad)
     }
 
-    pub fn read_word_pc(&self, pc: &mut u16) -> u16 {
-        let val = self.read_word(*pc);
-        *pc += 2;
-        val
+    // Advance the rest of the system by one M-cycle
+    fn tick(&mut self) {
+        if let Some(address) = self.video.dma_source() {
+            let val = self.mmu.read(address, self.video, self.timers, self.joypad);
+            self.video.dma_transfer(val);
+        }
+
+        self.timers.step(Self::M_CYCLE, self.mmu);
+        self.video.step(Self::M_CYCLE, self.mmu);
+        self.mmu.step(Self::M_CYCLE);
     }
 
-    pub fn write(&mut self, address: u16, val: u8) {
-        self.mmu
-            .write(address, val, self.video, self.timers, self.joypad)
+    fn peek(&self, address: u16) -> u8 {
+        self.mmu.read(address, self.video, self.timers, self.joypad)
     }
 
-    pub fn write_word(&mut self, address: u16, val: u16) {
-        self.mmu
-            .write_word(address, val, self.video, self.timers, self.joypad)
+    fn pending_interrupts(&self) -> Interrupts {
+        self.mmu.pending_interrupts()
+    }
+
+    fn reset_interrupt_flag(&mut self, interrupt: Interrupts) {
+        self.mmu.reset_interrupt_flag(interrupt)
+    }
+
+    fn reset_div(&mut self) {
+        self.timers.reset_div()
     }
 }
 
 impl Mmu {
     pub fn new(cartridge: Cartridge) -> Mmu {
         Mmu {
-            cartridge: cartridge,
+            cartridge,
             wram: [0; 0x2000],
             hram: [0; 0x7f],
             interrupt_flags: Interrupts::empty(),
@@ -80,35 +101,26 @@ impl Mmu {
     }
 
     pub fn read(&self, address: u16, video: &Video, timers: &Timers, joypad: &Joypad) -> u8 {
-        if video.dma_transfer_in_progess() && !(0xff80..0xfffe).contains(&address) {
-            return 0xff;
-        }
-
         match address {
             0x0000..=0x7fff => self.cartridge.read(address),
             0x8000..=0x9fff => video.read(address),
+            0xa000..=0xbfff => self.cartridge.read_ram(address),
             0xc000..=0xdfff => self.wram[address as usize - 0xc000],
             0xe000..=0xfdff => self.wram[address as usize - 0xe000],
             0xfe00..=0xfeff => video.read(address),
             0xff00 => joypad.read(),
             0xff04..=0xff07 => timers.read(address),
-            0xff0f => self.interrupt_flags.bits(),
+            // The upper three bits of IF are unused and always read as 1
+            0xff0f => self.interrupt_flags.bits() | 0xe0,
             //0xff01..=0xff02 => 0x00, // link cable NYI
             0xff40..=0xff4b => video.read(address),
             0xff80..=0xfffe => self.hram[address as usize - 0xff80],
             0xffff => self.enabled_interrupts.bits(),
-            _ => {
-                println!("Unimplemented read from {:x}", address);
-                0xff
-            }
+            // Unmapped I/O reads as open bus
+            _ => 0xff,
         }
     }
 
-    pub fn read_word(&self, address: u16, video: &Video, timers: &Timers, joypad: &Joypad) -> u16 {
-        self.read(address, video, timers, joypad) as u16
-            + (self.read(address + 1, video, timers, joypad) as u16 * 0x100)
-    }
-
     pub fn write(
         &mut self,
         address: u16,
@@ -117,51 +129,29 @@ impl Mmu {
         timers: &mut Timers,
         joypad: &mut Joypad,
     ) {
-        // if video.dma_transfer_in_progess() && !(0xff80..0xfffe).contains(&address) {
-        //     return;
-        // }
-
         match address {
             0x0000..=0x7fff => self.cartridge.write(address, val),
-            0x8000..=0x9fff => video.write(address, val, self, timers, joypad),
+            0x8000..=0x9fff => video.write(address, val, self),
+            0xa000..=0xbfff => self.cartridge.write_ram(address, val),
             0xc000..=0xdfff => self.wram[address as usize - 0xc000] = val,
             0xe000..=0xfdff => self.wram[address as usize - 0xe000] = val,
-            0xfe00..=0xfeff => video.write(address, val, self, timers, joypad),
+            0xfe00..=0xfeff => video.write(address, val, self),
             0xff00 => joypad.write(val),
-            0xff01 => {
-                self.serial_debug.push(val);
-                println!("serial: {:?}", self.serial_debug)
-            } // link cable, but used by test roms too!
+            // link cable, but used by test roms too!
+            0xff01 => self.serial_debug.push(val),
             0xff02 => {} // link cable, NYI
             0xff04..=0xff07 => timers.write(address, val),
             0xff0f => self.interrupt_flags = Interrupts::from_bits_retain(val),
             0xff10..=0xff26 => {} // sound, nyi
-            0xff40..=0xff4b => video.write(address, val, self, timers, joypad),
+            0xff40..=0xff4b => video.write(address, val, self),
             // Invalid I/O addresses
             0xff7f => {}
             0xff80..=0xfffe => self.hram[address as usize - 0xff80] = val,
-            0xffff => {
-                self.enabled_interrupts = Interrupts::from_bits_retain(val);
-                println!("enabled interrupts: {:?}", self.enabled_interrupts)
-            }
-            _ => {
-                println!("Unimplemented write to {:x}", address)
-            }
+            0xffff => self.enabled_interrupts = Interrupts::from_bits_retain(val),
+            _ => {}
         }
     }
 
-    pub fn write_word(
-        &mut self,
-        address: u16,
-        val: u16,
-        video: &mut Video,
-        timers: &mut Timers,
-        joypad: &mut Joypad,
-    ) {
-        self.write(address, (val & 0xff) as u8, video, timers, joypad);
-        self.write(address + 1, (val >> 8) as u8, video, timers, joypad);
-    }
-
     pub fn set_interrupt_flag(&mut self, interrupt: Interrupts) {
         self.interrupt_flags.insert(interrupt)
     }
@@ -170,11 +160,30 @@ impl Mmu {
         self.interrupt_flags.remove(interrupt)
     }
 
-    pub fn interrupt_flags(&self) -> Interrupts {
-        self.interrupt_flags
+    pub fn cartridge(&self) -> &Cartridge {
+        &self.cartridge
+    }
+
+    pub fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cartridge
+    }
+
+    // Cartridges with a clock count time along with the rest of the system
+    pub fn step(&mut self, cycles: Cycles) {
+        self.cartridge.step(cycles)
+    }
+
+    pub fn serial_output(&self) -> &[u8] {
+        &self.serial_debug
     }
 
     pub fn enabled_interrupts(&self) -> Interrupts {
         self.enabled_interrupts
     }
+
+    pub fn pending_interrupts(&self) -> Interrupts {
+        self.interrupt_flags
+            .intersection(self.enabled_interrupts)
+            .intersection(Interrupts::SOURCES)
+    }
 }
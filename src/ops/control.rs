@@ -1,17 +1,28 @@
-use crate::cpu::Cycles;
+use crate::bus::Bus;
+use crate::cpu::Flags;
 
-pub fn nop() -> Cycles {
-    Cycles(4)
+pub fn di(ime: &mut bool, ime_scheduled: &mut bool) {
+    *ime = false;
+    *ime_scheduled = false;
 }
 
-pub fn di(ime: &mut bool) -> Cycles {
-    println!("master interrupt disabled");
-    *ime = false;
-    Cycles(4)
+pub fn ei(ime_scheduled: &mut bool) {
+    *ime_scheduled = true;
+}
+
+pub fn stop(stopped: &mut bool, bus: &mut impl Bus) {
+    bus.reset_div();
+    *stopped = true;
+}
+
+pub fn scf(f: &mut Flags) {
+    f.remove(Flags::N);
+    f.remove(Flags::H);
+    f.insert(Flags::C);
 }
 
-pub fn ei(ime: &mut bool) -> Cycles {
-    println!("master interrupt enabled");
-    *ime = true;
-    Cycles(4)
+pub fn ccf(f: &mut Flags) {
+    f.remove(Flags::N);
+    f.remove(Flags::H);
+    f.toggle(Flags::C);
 }
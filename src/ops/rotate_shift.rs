@@ -1,54 +1,114 @@
-use crate::cpu::{Cycles, Flags};
+use crate::cpu::Flags;
 
-pub fn rlca(a: &mut u8, f: &mut Flags) -> Cycles {
-    let (shifted_a, overflowed) = a.overflowing_shl(1);
+pub fn rlc(r: &mut u8, f: &mut Flags) {
+    let res = r.rotate_left(1);
 
-    *a = if overflowed { shifted_a & 1 } else { shifted_a };
-    *f = if overflowed { Flags::C } else { Flags::empty() };
+    f.set(Flags::Z, res == 0);
+    f.remove(Flags::N);
+    f.remove(Flags::H);
+    f.set(Flags::C, *r & 0x80 != 0);
 
-    Cycles(4)
+    *r = res;
 }
 
-pub fn rla(a: &mut u8, f: &mut Flags) -> Cycles {
-    let (shifted_a, overflowed) = a.overflowing_shl(1);
+pub fn rrc(r: &mut u8, f: &mut Flags) {
+    let res = r.rotate_right(1);
 
-    *a = if f.contains(Flags::C) {
-        shifted_a & 1
-    } else {
-        shifted_a
-    };
-    *f = if overflowed { Flags::C } else { Flags::empty() };
+    f.set(Flags::Z, res == 0);
+    f.remove(Flags::N);
+    f.remove(Flags::H);
+    f.set(Flags::C, *r & 0x01 != 0);
 
-    Cycles(4)
+    *r = res;
 }
 
-pub fn rrca(a: &mut u8, f: &mut Flags) -> Cycles {
-    let (shifted_a, overflowed) = a.overflowing_shr(1);
+pub fn rl(r: &mut u8, f: &mut Flags) {
+    let carry = if f.contains(Flags::C) { 1 } else { 0 };
+    let res = (*r << 1) | carry;
 
-    *a = if overflowed {
-        shifted_a & 0xf0
-    } else {
-        shifted_a
-    };
-    *f = if overflowed { Flags::C } else { Flags::empty() };
+    f.set(Flags::Z, res == 0);
+    f.remove(Flags::N);
+    f.remove(Flags::H);
+    f.set(Flags::C, *r & 0x80 != 0);
 
-    Cycles(4)
+    *r = res;
 }
 
-pub fn rra(a: &mut u8, f: &mut Flags) -> Cycles {
-    let (shifted_a, overflowed) = a.overflowing_shr(1);
+pub fn rr(r: &mut u8, f: &mut Flags) {
+    let carry = if f.contains(Flags::C) { 0x80 } else { 0 };
+    let res = (*r >> 1) | carry;
 
-    *a = if f.contains(Flags::C) {
-        shifted_a & 0xf0
-    } else {
-        shifted_a
-    };
-    *f = if overflowed { Flags::C } else { Flags::empty() };
+    f.set(Flags::Z, res == 0);
+    f.remove(Flags::N);
+    f.remove(Flags::H);
+    f.set(Flags::C, *r & 0x01 != 0);
 
-    Cycles(4)
+    *r = res;
 }
 
-pub fn swap_r(r: &mut u8) -> Cycles {
-    *r = ((*r & 0xf) << 4) & (*r >> 4);
-    Cycles(4)
+pub fn sla(r: &mut u8, f: &mut Flags) {
+    let res = *r << 1;
+
+    f.set(Flags::Z, res == 0);
+    f.remove(Flags::N);
+    f.remove(Flags::H);
+    f.set(Flags::C, *r & 0x80 != 0);
+
+    *r = res;
+}
+
+pub fn sra(r: &mut u8, f: &mut Flags) {
+    let res = (*r >> 1) | (*r & 0x80);
+
+    f.set(Flags::Z, res == 0);
+    f.remove(Flags::N);
+    f.remove(Flags::H);
+    f.set(Flags::C, *r & 0x01 != 0);
+
+    *r = res;
+}
+
+pub fn srl(r: &mut u8, f: &mut Flags) {
+    let res = *r >> 1;
+
+    f.set(Flags::Z, res == 0);
+    f.remove(Flags::N);
+    f.remove(Flags::H);
+    f.set(Flags::C, *r & 0x01 != 0);
+
+    *r = res;
+}
+
+pub fn swap(r: &mut u8, f: &mut Flags) {
+    let res = r.rotate_left(4);
+
+    f.set(Flags::Z, res == 0);
+    f.remove(Flags::N);
+    f.remove(Flags::H);
+    f.remove(Flags::C);
+
+    *r = res;
+}
+
+// The accumulator rotates always clear Z, unlike their CB-prefixed
+// counterparts.
+
+pub fn rlca(a: &mut u8, f: &mut Flags) {
+    rlc(a, f);
+    f.remove(Flags::Z);
+}
+
+pub fn rla(a: &mut u8, f: &mut Flags) {
+    rl(a, f);
+    f.remove(Flags::Z);
+}
+
+pub fn rrca(a: &mut u8, f: &mut Flags) {
+    rrc(a, f);
+    f.remove(Flags::Z);
+}
+
+pub fn rra(a: &mut u8, f: &mut Flags) {
+    rr(a, f);
+    f.remove(Flags::Z);
 }
@@ -1,11 +1,15 @@
-use crate::cpu::Cycles;
+use crate::cpu::Flags;
 
-pub fn set_n_r(n: u8, r: &mut u8) -> Cycles {
+pub fn bit(n: u8, r: u8, f: &mut Flags) {
+    f.set(Flags::Z, r & (1 << n) == 0);
+    f.remove(Flags::N);
+    f.insert(Flags::H);
+}
+
+pub fn set(n: u8, r: &mut u8) {
     *r |= 1 << n;
-    Cycles(8)
 }
 
-pub fn res_n_r(n: u8, r: &mut u8) -> Cycles {
-    *r ^= 1 << n;
-    Cycles(8)
+pub fn res(n: u8, r: &mut u8) {
+    *r &= !(1 << n);
 }
@@ -1,25 +1,129 @@
+use std::fmt;
+
+pub const NINTENDO_LOGO: [u8; 48] = [
+    0xce, 0xed, 0x66, 0x66, 0xcc, 0x0d, 0x00, 0x0b, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0c, 0x00, 0x0d,
+    0x00, 0x08, 0x11, 0x1f, 0x88, 0x89, 0x00, 0x0e, 0xdc, 0xcc, 0x6e, 0xe6, 0xdd, 0xdd, 0xd9, 0x99,
+    0xbb, 0xbb, 0x67, 0x63, 0x6e, 0x0e, 0xec, 0xcc, 0xdd, 0xdc, 0x99, 0x9f, 0xbb, 0xb9, 0x33, 0x3e,
+];
+
 pub struct RomInfo {
     pub title: String,
+    // Later cartridges shortened the title to make room for this
+    pub manufacturer_code: Option<String>,
+    pub cgb_support: CgbSupport,
+    pub licensee: Licensee,
+    pub sgb_support: bool,
+    pub cartridge_type: u8,
     pub mbc_type: MbcType,
+    // Bytes of ROM and external RAM on the cartridge
+    pub rom_size: usize,
+    pub ram_size: usize,
+    pub destination: Destination,
+    pub version: u8,
+    // Whether external RAM keeps its contents while switched off
+    pub battery: bool,
+    pub rtc: bool,
+    pub rumble: bool,
+    pub camera: bool,
+    // Checksum of 0x134-0x14c, which the boot ROM verifies
     pub checksum: u8,
+    // Sum of every other byte in the ROM, which nothing checks
+    pub global_checksum: u16,
+    // What the checksums should be, worked out from the ROM itself
+    pub computed_checksum: u8,
+    pub computed_global_checksum: u16,
+}
+
+// Reasons a file can't be a Game Boy ROM
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeaderError {
+    // Too short to hold the header, which ends at 0x150
+    TooSmall(usize),
+    BadLogo,
+    BadHeaderChecksum { expected: u8, computed: u8 },
+    UnknownCartridgeType(u8),
 }
 
-#[derive(Clone, Copy, Debug)]
+// Signs of a corrupt dump that don't stop it from running
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Warning {
+    HeaderChecksum { expected: u8, computed: u8 },
+    GlobalChecksum { expected: u16, computed: u16 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MbcType {
     NoMBC,
     MBC1,
+    // Several MBC1 games on one cartridge, with a menu to pick between them
+    MBC1M,
     MBC2,
     MMM01,
     MBC3,
     MBC4,
     MBC5,
+    MBC7,
+    HuC1,
+    HuC3,
+    PocketCamera,
     Unknown,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CgbSupport {
+    None,
+    // Works on both, with extra features on a Game Boy Color
+    Enhanced,
+    Only,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Licensee {
+    Old(u8),
+    // Two ASCII characters, used when the old code is 0x33
+    New(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Destination {
+    Japan,
+    Overseas,
+}
+
 impl RomInfo {
-    pub fn new(rom: &[u8]) -> RomInfo {
+    // Only checks what's needed to tell what the cartridge is. Checksum
+    // mismatches are left to `warnings` and `validate`.
+    pub fn new(rom: &[u8]) -> Result<RomInfo, HeaderError> {
+        if rom.len() < 0x150 {
+            return Err(HeaderError::TooSmall(rom.len()));
+        }
+        let header = mmm01_menu(rom).unwrap_or(rom);
+        if header[0x104..0x134] != NINTENDO_LOGO {
+            return Err(HeaderError::BadLogo);
+        }
+
+        let cgb_support = match header[0x143] {
+            0x80 => CgbSupport::Enhanced,
+            0xc0 => CgbSupport::Only,
+            _ => CgbSupport::None,
+        };
+
+        // The manufacturer code can only be told apart from a long title by
+        // looking like one
+        let manufacturer_code = &header[0x13f..0x143];
+        let manufacturer_code = (cgb_support != CgbSupport::None
+            && manufacturer_code
+                .iter()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()))
+        .then(|| String::from_utf8_lossy(manufacturer_code).into_owned());
+
+        let title_end = match (&manufacturer_code, cgb_support) {
+            (Some(_), _) => 0x13f,
+            (None, CgbSupport::None) => 0x144,
+            (None, _) => 0x143,
+        };
         let mut title = String::new();
-        for character in rom[0x134..0x144].iter() {
+        for character in header[0x134..title_end].iter() {
             if *character == 0u8 {
                 break;
             }
@@ -27,7 +131,13 @@ impl RomInfo {
             title.push(*character as char)
         }
 
-        let mbc_type = match rom[0x147] {
+        let licensee = match header[0x14b] {
+            0x33 => Licensee::New(String::from_utf8_lossy(&header[0x144..0x146]).into_owned()),
+            code => Licensee::Old(code),
+        };
+
+        let cartridge_type = header[0x147];
+        let mbc_type = match cartridge_type {
             0x00 | 0x08 | 0x09 => MbcType::NoMBC,
             0x01..=0x03 => MbcType::MBC1,
             0x05 | 0x06 => MbcType::MBC2,
@@ -35,13 +145,217 @@ impl RomInfo {
             0x0f..=0x13 => MbcType::MBC3,
             0x15..=0x17 => MbcType::MBC4,
             0x19..=0x1e => MbcType::MBC5,
-            _ => MbcType::Unknown,
+            0xfe => MbcType::HuC3,
+            0xff => MbcType::HuC1,
+            0xfc => MbcType::PocketCamera,
+            0x22 => MbcType::MBC7,
+            0x20 | 0xfd => MbcType::Unknown,
+            _ => return Err(HeaderError::UnknownCartridgeType(cartridge_type)),
+        };
+
+        let mbc_type = match mbc_type {
+            MbcType::MBC1 if is_multicart(rom) => MbcType::MBC1M,
+            _ => mbc_type,
         };
 
-        RomInfo {
-            title: title,
-            mbc_type: mbc_type,
-            checksum: rom[0x14d],
+        let rom_size = match header[0x148] {
+            code @ 0x00..=0x08 => 0x8000 << code,
+            // Only used by a handful of unofficial sizes
+            0x52 => 72 * 0x4000,
+            0x53 => 80 * 0x4000,
+            0x54 => 96 * 0x4000,
+            _ => 0,
+        };
+
+        let ram_size = match header[0x149] {
+            0x01 => 0x800,
+            0x02 => 0x2000,
+            0x03 => 0x8000,
+            0x04 => 0x20000,
+            0x05 => 0x10000,
+            _ => 0,
+        };
+
+        let battery = matches!(
+            cartridge_type,
+            0x03 | 0x06
+                | 0x09
+                | 0x0d
+                | 0x0f
+                | 0x10
+                | 0x13
+                | 0x1b
+                | 0x1e
+                | 0x22
+                | 0xfc
+                | 0xfe
+                | 0xff
+        );
+
+        let computed_checksum = header[0x134..0x14d]
+            .iter()
+            .fold(0u8, |x, byte| x.wrapping_sub(*byte).wrapping_sub(1));
+        let computed_global_checksum = rom
+            .iter()
+            .enumerate()
+            .filter(|(address, _)| !matches!(address, 0x14e | 0x14f))
+            .fold(0u16, |sum, (_, byte)| sum.wrapping_add(*byte as u16));
+
+        Ok(RomInfo {
+            title,
+            manufacturer_code,
+            cgb_support,
+            licensee,
+            sgb_support: header[0x146] == 0x03,
+            cartridge_type,
+            mbc_type,
+            rom_size,
+            ram_size,
+            destination: if header[0x14a] == 0x00 {
+                Destination::Japan
+            } else {
+                Destination::Overseas
+            },
+            version: header[0x14c],
+            battery,
+            rtc: matches!(cartridge_type, 0x0f | 0x10 | 0xfe),
+            rumble: matches!(cartridge_type, 0x1c..=0x1e),
+            camera: cartridge_type == 0xfc,
+            checksum: header[0x14d],
+            global_checksum: u16::from_be_bytes([header[0x14e], header[0x14f]]),
+            computed_checksum,
+            computed_global_checksum,
+        })
+    }
+
+    pub fn warnings(&self) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        if self.checksum != self.computed_checksum {
+            warnings.push(Warning::HeaderChecksum {
+                expected: self.checksum,
+                computed: self.computed_checksum,
+            });
+        }
+        if self.global_checksum != self.computed_global_checksum {
+            warnings.push(Warning::GlobalChecksum {
+                expected: self.global_checksum,
+                computed: self.computed_global_checksum,
+            });
+        }
+        warnings
+    }
+
+    // Whether the boot ROM would accept the cartridge. It locks up on a bad
+    // header checksum, but ignores the global one.
+    pub fn validate(&self) -> Result<(), HeaderError> {
+        if self.checksum != self.computed_checksum {
+            return Err(HeaderError::BadHeaderChecksum {
+                expected: self.checksum,
+                computed: self.computed_checksum,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HeaderError::TooSmall(len) => write!(f, "only {} bytes, too small for a header", len),
+            HeaderError::BadLogo => write!(f, "the Nintendo logo doesn't match"),
+            HeaderError::BadHeaderChecksum { expected, computed } => write!(
+                f,
+                "header checksum is {:02x}, should be {:02x}",
+                expected, computed
+            ),
+            HeaderError::UnknownCartridgeType(code) => {
+                write!(f, "unknown cartridge type {:02x}", code)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HeaderError {}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::HeaderChecksum { expected, computed } => write!(
+                f,
+                "header checksum is {:02x}, should be {:02x}",
+                expected, computed
+            ),
+            Warning::GlobalChecksum { expected, computed } => write!(
+                f,
+                "global checksum is {:04x}, should be {:04x}",
+                expected, computed
+            ),
         }
     }
 }
+
+impl fmt::Display for Licensee {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Licensee::Old(code) => write!(f, "{:02x}", code),
+            Licensee::New(code) => write!(f, "{} (new)", code),
+        }
+    }
+}
+
+impl fmt::Display for RomInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut hardware = Vec::new();
+        for (present, name) in [
+            (self.ram_size > 0, "RAM"),
+            (self.battery, "battery"),
+            (self.rtc, "timer"),
+            (self.rumble, "rumble"),
+            (self.camera, "camera"),
+        ] {
+            if present {
+                hardware.push(name);
+            }
+        }
+
+        writeln!(f, "Title:            {}", self.title)?;
+        if let Some(code) = &self.manufacturer_code {
+            writeln!(f, "Manufacturer:     {}", code)?;
+        }
+        writeln!(f, "Licensee:         {}", self.licensee)?;
+        writeln!(f, "CGB support:      {:?}", self.cgb_support)?;
+        writeln!(f, "SGB support:      {}", self.sgb_support)?;
+        writeln!(
+            f,
+            "Cartridge type:   {:02x} ({:?})",
+            self.cartridge_type, self.mbc_type
+        )?;
+        writeln!(f, "Hardware:         {}", hardware.join(", "))?;
+        writeln!(f, "ROM size:         {} KiB", self.rom_size / 1024)?;
+        writeln!(f, "RAM size:         {} KiB", self.ram_size / 1024)?;
+        writeln!(f, "Destination:      {:?}", self.destination)?;
+        writeln!(f, "Version:          {}", self.version)?;
+        writeln!(f, "Header checksum:  {:02x}", self.checksum)?;
+        write!(f, "Global checksum:  {:04x}", self.global_checksum)
+    }
+}
+
+// MMM01 collections start up with the menu in the last 32KiB mapped in, and
+// the header that describes the whole cartridge is the menu's. The one at the
+// start of the ROM belongs to the first game.
+fn mmm01_menu(rom: &[u8]) -> Option<&[u8]> {
+    let menu = &rom[rom.len().checked_sub(0x8000)?..];
+    (menu.len() >= 0x150
+        && menu[0x104..0x134] == NINTENDO_LOGO
+        && matches!(menu[0x147], 0x0b..=0x0d))
+    .then_some(menu)
+}
+
+// Each game on a multicart has its own header, so there's a copy of the logo
+// at the start of every 256KiB after the menu's
+fn is_multicart(rom: &[u8]) -> bool {
+    (0x40000..rom.len()).step_by(0x40000).any(|base| {
+        rom.get(base + 0x104..)
+            .is_some_and(|header| header.starts_with(&NINTENDO_LOGO))
+    })
+}
@@ -0,0 +1,105 @@
+use crate::cartridge::Cartridge;
+use crate::mbc::huc3::Clock;
+use crate::mbc::rtc::{self, Rtc};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+
+// Saves are the raw contents of cartridge RAM, as written by most other
+// emulators, so they can be moved between them freely. Cartridges with a
+// clock have the VBA-M/BGB trailer appended: the clock registers and their
+// latched copies as 32-bit little endian values, then the time of saving as
+// a 64-bit UNIX timestamp. Some emulators only write 32 bits of timestamp.
+const RTC_TRAILER_SIZE: usize = 48;
+const SHORT_RTC_TRAILER_SIZE: usize = 44;
+
+// HuC3 cartridges get SameBoy's trailer instead: the time of saving as a
+// 64-bit UNIX timestamp, then the minute of the day, the day counter and the
+// alarm's minute and day as 16-bit little endian values, then whether the
+// alarm is on. The alarm isn't emulated so it's saved as off, and the clock
+// chip's memory has nowhere to go so it's lost.
+const HUC3_TRAILER_SIZE: usize = 8 + 2 + 2 + 2 + 2 + 1;
+
+// Restore a save file into the cartridge. A missing file just means there's
+// nothing saved yet.
+pub fn load(path: &Path, cartridge: &mut Cartridge) -> io::Result<()> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(error) => return Err(error),
+    };
+
+    let ram_size = cartridge.battery_ram().map_or(0, |ram| ram.len());
+    let (ram, trailer) = data.split_at(ram_size.min(data.len()));
+    cartridge.load_battery_ram(ram);
+
+    if let Some(rtc) = cartridge.rtc_mut() {
+        match trailer.len() {
+            RTC_TRAILER_SIZE | SHORT_RTC_TRAILER_SIZE => load_rtc(trailer, rtc),
+            0 => println!("No clock in save file, starting from zero"),
+            len => println!("Ignoring unrecognised {} byte clock in save file", len),
+        }
+    } else if let Some(clock) = cartridge.huc3_clock_mut() {
+        match trailer.len() {
+            HUC3_TRAILER_SIZE => load_huc3_clock(trailer, clock),
+            0 => println!("No clock in save file, starting from zero"),
+            len => println!("Ignoring unrecognised {} byte clock in save file", len),
+        }
+    }
+    Ok(())
+}
+
+pub fn store(path: &Path, cartridge: &Cartridge) -> io::Result<()> {
+    let mut data = cartridge.battery_ram().unwrap_or_default().to_vec();
+    if let Some(rtc) = cartridge.rtc() {
+        store_rtc(rtc, &mut data);
+    } else if let Some(clock) = cartridge.huc3_clock() {
+        store_huc3_clock(clock, &mut data);
+    }
+
+    if data.is_empty() {
+        return Ok(());
+    }
+    fs::write(path, data)
+}
+
+fn load_rtc(trailer: &[u8], rtc: &mut Rtc) {
+    let word = |i: usize| trailer[i * 4];
+    let registers = [0, 1, 2, 3, 4].map(word);
+    let latched = [5, 6, 7, 8, 9].map(word);
+
+    let mut timestamp = [0; 8];
+    let stored = &trailer[40..];
+    timestamp[..stored.len()].copy_from_slice(stored);
+
+    rtc.restore(registers, latched);
+    let saved_at = rtc::from_unix_time(u64::from_le_bytes(timestamp));
+    rtc.advance(rtc::seconds_since(saved_at));
+}
+
+fn store_rtc(rtc: &Rtc, data: &mut Vec<u8>) {
+    for val in rtc.registers().into_iter().chain(rtc.latched_registers()) {
+        data.extend_from_slice(&(val as u32).to_le_bytes());
+    }
+    data.extend_from_slice(&rtc::to_unix_time(SystemTime::now()).to_le_bytes());
+}
+
+fn load_huc3_clock(trailer: &[u8], clock: &mut Clock) {
+    let mut timestamp = [0; 8];
+    timestamp.copy_from_slice(&trailer[..8]);
+    let minutes = u16::from_le_bytes([trailer[8], trailer[9]]);
+    let days = u16::from_le_bytes([trailer[10], trailer[11]]);
+
+    clock.restore(minutes, days);
+    let saved_at = rtc::from_unix_time(u64::from_le_bytes(timestamp));
+    clock.advance(rtc::seconds_since(saved_at));
+}
+
+fn store_huc3_clock(clock: &Clock, data: &mut Vec<u8>) {
+    data.extend_from_slice(&rtc::to_unix_time(SystemTime::now()).to_le_bytes());
+    data.extend_from_slice(&clock.minutes().to_le_bytes());
+    data.extend_from_slice(&clock.days().to_le_bytes());
+    // No alarm
+    data.extend_from_slice(&[0; 5]);
+}
@@ -0,0 +1,88 @@
+use crate::cpu::{Cycles, CLOCK_SPEED};
+use crate::gameboy::Gameboy;
+use crate::rom_info::HeaderError;
+
+// Roughly a minute of emulated time, longer than any of blargg's suites take
+pub const DEFAULT_BUDGET: Cycles = Cycles(60 * CLOCK_SPEED.0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    Failed,
+    // The cycle budget ran out before the ROM reported a result
+    TimedOut,
+}
+
+#[derive(Debug)]
+pub struct TestResult {
+    pub outcome: Outcome,
+    pub serial: String,
+    pub cycles: Cycles,
+}
+
+// Run a test ROM without a display until it reports a result over serial, as
+// blargg's ROMs do
+pub fn run(rom: Vec<u8>, budget: Cycles) -> Result<TestResult, HeaderError> {
+    let mut serial_len = 0;
+
+    run_until(rom, budget, |gb| {
+        // Only rescan the output when something new was sent
+        let serial = gb.serial_output();
+        if serial.len() == serial_len {
+            return None;
+        }
+        serial_len = serial.len();
+
+        let text = String::from_utf8_lossy(serial);
+        if text.contains("Passed") {
+            Some(Outcome::Passed)
+        } else if text.contains("Failed") {
+            Some(Outcome::Failed)
+        } else {
+            None
+        }
+    })
+}
+
+// Mooneye's ROMs finish by executing LD B,B, with the Fibonacci numbers in
+// B, C, D, E, H and L if they passed
+pub fn run_mooneye(rom: Vec<u8>, budget: Cycles) -> Result<TestResult, HeaderError> {
+    run_until(rom, budget, |gb| {
+        if !gb.take_breakpoint() {
+            return None;
+        }
+
+        let r = gb.registers();
+        if [r.b, r.c, r.d, r.e, r.h, r.l] == [3, 5, 8, 13, 21, 34] {
+            Some(Outcome::Passed)
+        } else {
+            Some(Outcome::Failed)
+        }
+    })
+}
+
+fn run_until(
+    rom: Vec<u8>,
+    budget: Cycles,
+    mut finished: impl FnMut(&mut Gameboy) -> Option<Outcome>,
+) -> Result<TestResult, HeaderError> {
+    let mut gb = Gameboy::new(rom)?;
+    let mut cycles = Cycles(0);
+
+    let outcome = loop {
+        if cycles >= budget {
+            break Outcome::TimedOut;
+        }
+        cycles += gb.step();
+
+        if let Some(outcome) = finished(&mut gb) {
+            break outcome;
+        }
+    };
+
+    Ok(TestResult {
+        outcome,
+        serial: String::from_utf8_lossy(gb.serial_output()).into_owned(),
+        cycles,
+    })
+}
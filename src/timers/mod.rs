@@ -1,2 +1,3 @@
 pub mod cycle_timer;
+#[allow(clippy::module_inception)]
 pub mod timers;
@@ -32,6 +32,12 @@ impl Control {
     }
 }
 
+impl Default for Timers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Timers {
     const DIV_INCREMENT_TIME: Cycles = Cycles(1024);
 
@@ -69,6 +75,11 @@ impl Timers {
         }
     }
 
+    pub fn reset_div(&mut self) {
+        self.div = 0;
+        self.div_timer.reset()
+    }
+
     pub fn read(&self, address: u16) -> u8 {
         match address {
             0xff04 => self.div,
@@ -82,17 +93,12 @@ impl Timers {
 
     pub fn write(&mut self, address: u16, val: u8) {
         match address {
-            0xff04 => {
-                self.div = 0;
-                self.div_timer.reset()
-            }
+            0xff04 => self.reset_div(),
             0xff05 => self.counter = val,
             0xff06 => self.modulo = val,
             0xff07 => {
-                println!("Timer set to {:2x}", val);
                 self.control = Control(val);
                 self.timer = if self.control.enabled() {
-                    println!("Timer enabled for {}", self.control.interval().0);
                     Some(CycleTimer::new(self.control.interval()))
                 } else {
                     None
@@ -1,6 +1,6 @@
 pub mod palette;
-mod ppu;
 mod tile;
+#[allow(clippy::module_inception)]
 mod video;
 
 pub use video::Video;
@@ -1 +0,0 @@
-struct PixelProcessingUnit {}
@@ -40,8 +40,6 @@ impl Tile {
         let low_bit = (low_byte >> shift) & 0b1;
         let high_bit = (high_byte >> shift) & 0b1;
 
-        let out = (high_bit << 1) + low_bit;
-
-        out
+        (high_bit << 1) + low_bit
     }
 }
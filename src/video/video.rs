@@ -1,11 +1,8 @@
-use std::thread::current;
-
 use super::{palette::Palette, tile::Tile};
 use crate::{
     cpu::{Cycles, Interrupts},
-    joypad::Joypad,
     mmu::Mmu,
-    timers::{cycle_timer::CycleTimer, timers::Timers},
+    timers::cycle_timer::CycleTimer,
 };
 use bitflags::bitflags;
 use rgb::RGBA8;
@@ -16,7 +13,7 @@ pub struct Video {
 
     display: [[RGBA8; Video::RESOLUTION_X as _]; Video::RESOLUTION_Y as _],
 
-    dma_transfer_timer: Option<CycleTimer>,
+    dma: Option<Dma>,
 
     control: Control,
     stat_interrupts: StatInterruptCondition,
@@ -27,6 +24,14 @@ pub struct Video {
 
     state: State,
     frame_ready: bool,
+
+    // Reported in place of the real LY, for matching reference traces
+    fixed_lcd_y: Option<u8>,
+}
+
+struct Dma {
+    source: u16,
+    transferred: u16,
 }
 
 enum State {
@@ -42,11 +47,17 @@ enum State {
 }
 
 enum RenderState {
-    OAM,
+    Oam,
     RenderingLine,
     HBlank,
 }
 
+impl Default for Video {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Video {
     const OAM_TIME: Cycles = Cycles(320);
     const LINE_TIME: Cycles = Cycles(1824);
@@ -61,7 +72,7 @@ impl Video {
             oam: [0; 0xa0],
             display: [[Palette::MONOCHROME_GREEN.color(3); Self::RESOLUTION_X as _];
                 Self::RESOLUTION_Y as _],
-            dma_transfer_timer: None,
+            dma: None,
 
             control: Control::from_bits_retain(0x91),
             lcd_y_compare: 0,
@@ -73,10 +84,11 @@ impl Video {
             state: State::Render {
                 line: 0,
                 line_timer: CycleTimer::new(Self::LINE_TIME),
-                state: RenderState::OAM,
+                state: RenderState::Oam,
             },
 
             frame_ready: false,
+            fixed_lcd_y: None,
         }
     }
 
@@ -96,7 +108,7 @@ impl Video {
             // 0xff41 => self.stat,
             0xff42 => self.background.y,
             0xff43 => self.background.x,
-            0xff44 => self.lcd_y(),
+            0xff44 => self.fixed_lcd_y.unwrap_or_else(|| self.lcd_y()),
             // 0xff45 => self.lyc,
             // 0xff47 => self.bgp,
             // 0xff48 => self.obp0,
@@ -107,14 +119,7 @@ impl Video {
         }
     }
 
-    pub fn write(
-        &mut self,
-        address: u16,
-        val: u8,
-        mmu: &mut Mmu,
-        timers: &mut Timers,
-        joypad: &mut Joypad,
-    ) {
+    pub fn write(&mut self, address: u16, val: u8, mmu: &mut Mmu) {
         match address {
             0x8000..=0x9fff => self.write_vram(address, val),
             0xfe00..=0xfe9f => self.write_oam(address, val),
@@ -134,7 +139,7 @@ impl Video {
                     self.state = State::Render {
                         line: 0,
                         line_timer: CycleTimer::new(Self::LINE_TIME),
-                        state: RenderState::OAM,
+                        state: RenderState::Oam,
                     }
                 }
             }
@@ -144,7 +149,7 @@ impl Video {
             0xff45 => {
                 self.lcd_y_compare = val;
             }
-            0xff46 => self.begin_dma_transfer(val, mmu, timers, joypad),
+            0xff46 => self.begin_dma_transfer(val),
             0xff47 => {
                 println!("bg palette control register unimplemented")
             }
@@ -158,20 +163,32 @@ impl Video {
         }
     }
 
-    fn begin_dma_transfer(&mut self, address: u8, mmu: &Mmu, timers: &Timers, joypad: &Joypad) {
-        let start_address = address as u16 * 0x100;
-
-        println!("Beginning DMA transfer from {:4x}", start_address);
+    fn begin_dma_transfer(&mut self, address: u8) {
+        self.dma = Some(Dma {
+            source: address as u16 * 0x100,
+            transferred: 0,
+        });
+    }
 
-        for i in 0..=0x9f {
-            self.oam[i] = mmu.read(start_address + i as u16, &self, timers, joypad)
-        }
+    pub fn dma_transfer_in_progess(&self) -> bool {
+        self.dma.is_some()
+    }
 
-        self.dma_transfer_timer = Some(CycleTimer::new(Cycles(580)));
+    // The address DMA reads from during the current M-cycle
+    pub fn dma_source(&self) -> Option<u16> {
+        self.dma.as_ref().map(|dma| dma.source + dma.transferred)
     }
 
-    pub fn dma_transfer_in_progess(&self) -> bool {
-        self.dma_transfer_timer.is_some()
+    // OAM DMA copies one byte per M-cycle, bypassing the usual OAM locking
+    pub fn dma_transfer(&mut self, val: u8) {
+        if let Some(dma) = &mut self.dma {
+            self.oam[dma.transferred as usize] = val;
+            dma.transferred += 1;
+
+            if dma.transferred as usize == self.oam.len() {
+                self.dma = None;
+            }
+        }
     }
 
     pub fn frame_ready(&self) -> bool {
@@ -190,7 +207,7 @@ impl Video {
         match &self.state {
             State::VBlank { .. } => true,
             State::Render { state, .. } => match state {
-                RenderState::OAM | RenderState::HBlank => true,
+                RenderState::Oam | RenderState::HBlank => true,
                 RenderState::RenderingLine => false,
             },
             State::Disabled => true,
@@ -229,17 +246,23 @@ impl Video {
             State::VBlank { .. } => true,
             State::Render { state, .. } => match state {
                 RenderState::HBlank => true,
-                RenderState::OAM | RenderState::RenderingLine => false,
+                RenderState::Oam | RenderState::RenderingLine => false,
             },
             State::Disabled => true,
         }
     }
 
+    // gameboy-doctor logs are recorded with LY reading 0x90 at all times, so
+    // that code polling for vblank runs the same regardless of PPU timing
+    pub fn fix_lcd_y(&mut self, val: u8) {
+        self.fixed_lcd_y = Some(val);
+    }
+
     fn lcd_y(&self) -> u8 {
         match &self.state {
             State::Render { line, .. } => *line,
             State::VBlank { timer } => {
-                Self::RESOLUTION_Y as u8 + (timer.counted().0 / Self::LINE_TIME.0) as u8
+                Self::RESOLUTION_Y + (timer.counted().0 / Self::LINE_TIME.0) as u8
             }
             State::Disabled => 0xff,
         }
@@ -260,14 +283,6 @@ impl Video {
     }
 
     pub fn step(&mut self, cycles: Cycles, mmu: &mut Mmu) {
-        if let Some(dma_transfer_timer) = &mut self.dma_transfer_timer {
-            dma_transfer_timer.tick(cycles);
-            if dma_transfer_timer.finished() {
-                println!("DMA transfer complete!");
-                self.dma_transfer_timer = None;
-            }
-        }
-
         let mut cycles_left = cycles;
 
         while cycles_left > Cycles(0) {
@@ -277,12 +292,11 @@ impl Video {
                     timer.tick(cycles_left);
                     if timer.finished() {
                         let overflow = timer.overflow();
-                        println!("Beginning render {:?}", self.control);
 
                         self.state = State::Render {
                             line: 0,
                             line_timer: CycleTimer::new(Self::LINE_TIME),
-                            state: RenderState::OAM,
+                            state: RenderState::Oam,
                         };
 
                         overflow.unwrap_or(Cycles(0))
@@ -298,7 +312,7 @@ impl Video {
                     line_timer.tick(Cycles(4));
 
                     match state {
-                        RenderState::OAM => {
+                        RenderState::Oam => {
                             if line_timer.counted() == Self::OAM_TIME {
                                 *state = RenderState::RenderingLine
                             }
@@ -346,7 +360,7 @@ impl Video {
                                                 7 - tile_x_offset
                                             };
 
-                                        for pixel in (start_pixel..=end_pixel) {
+                                        for pixel in start_pixel..=end_pixel {
                                             self.display[*line as usize]
                                                 [(pixel - tile_x_offset) as usize] = tile
                                                 .pixel_color(
@@ -367,14 +381,10 @@ impl Video {
                                     self.state = State::VBlank {
                                         timer: CycleTimer::new(Self::VBLANK_TIME),
                                     };
-                                    println!(
-                                        "Entering vblank, enabled interrupts {:?}",
-                                        mmu.enabled_interrupts()
-                                    );
                                     mmu.set_interrupt_flag(Interrupts::VBLANK);
                                     self.frame_ready = true
                                 } else {
-                                    *state = RenderState::OAM;
+                                    *state = RenderState::Oam;
                                     *line += 1;
                                     line_timer.reset();
                                 }
@@ -448,20 +458,11 @@ bitflags! {
     }
 }
 
-pub enum SpriteSize {
-    SINGLE_TILE,
-    DOUBLE_TILE,
-}
-
 impl Control {
     pub fn enabled(&self) -> bool {
         self.contains(Control::MASTER_CONTROL)
     }
 
-    pub fn sprites_enabled(&self) -> bool {
-        self.contains(Control::OBJ_ENABLED)
-    }
-
     pub fn background_enabled(&self) -> bool {
         self.contains(Control::BG_AND_WINDOW_ENABLED)
     }
@@ -493,12 +494,4 @@ impl Control {
             0x9c00
         }
     }
-
-    pub fn sprite_size(&self) -> SpriteSize {
-        if self.contains(Control::OBJ_SIZE) {
-            SpriteSize::SINGLE_TILE
-        } else {
-            SpriteSize::DOUBLE_TILE
-        }
-    }
 }
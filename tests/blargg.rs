@@ -0,0 +1,63 @@
+// Runs blargg's test suites headlessly. The ROMs aren't distributed with the
+// emulator; copy them into tests/roms/blargg/, e.g.
+// tests/roms/blargg/cpu_instrs/individual/01-special.gb, and run these with
+// `cargo test -- --ignored`.
+
+use missingnogmb::test_runner::{self, Outcome};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn roms(suite: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/roms/blargg")
+        .join(suite);
+    let entries = fs::read_dir(&dir)
+        .unwrap_or_else(|err| panic!("no ROMs for {} in {}: {}", suite, dir.display(), err));
+
+    let mut roms: Vec<PathBuf> = entries
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "gb"))
+        .collect();
+    roms.sort();
+    assert!(
+        !roms.is_empty(),
+        "no ROMs for {} in {}",
+        suite,
+        dir.display()
+    );
+    roms
+}
+
+fn run_suite(suite: &str) {
+    let mut failures = Vec::new();
+    for rom in roms(suite) {
+        let result =
+            test_runner::run(fs::read(&rom).unwrap(), test_runner::DEFAULT_BUDGET).unwrap();
+        let name = rom.file_name().unwrap().to_string_lossy();
+        println!("{:<40} {:?}", name, result.outcome);
+
+        if result.outcome != Outcome::Passed {
+            failures.push(format!("{}: {:?}\n{}", name, result.outcome, result.serial));
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+#[ignore = "needs the ROMs in tests/roms/blargg/"]
+fn cpu_instrs() {
+    run_suite("cpu_instrs/individual");
+}
+
+#[test]
+#[ignore = "needs the ROMs in tests/roms/blargg/"]
+fn instr_timing() {
+    run_suite("instr_timing");
+}
+
+#[test]
+#[ignore = "needs the ROMs in tests/roms/blargg/"]
+fn mem_timing() {
+    run_suite("mem_timing/individual");
+}
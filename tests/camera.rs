@@ -0,0 +1,38 @@
+// Pictures from sources that don't follow the rules
+
+mod common;
+
+use missingnogmb::cpu::CLOCK_SPEED;
+use missingnogmb::mbc::image_source::{ImageSource, StaticImage};
+
+struct Sized(usize);
+
+impl ImageSource for Sized {
+    fn frame(&mut self) -> Vec<u8> {
+        vec![0x80; self.0]
+    }
+}
+
+#[test]
+fn static_image_needs_enough_pixels() {
+    assert!(StaticImage::new(&[0; 12], 4, 3).is_ok());
+    assert!(StaticImage::new(&[0; 11], 4, 3).is_err());
+    assert!(StaticImage::new(&[], 0, 0).is_err());
+}
+
+#[test]
+fn badly_sized_frames_are_captured() {
+    let mut cartridge = common::cartridge(0xfc, 0x8000, 0x20000);
+
+    // Enable RAM writes and map in the sensor's registers
+    cartridge.write(0x0000, 0x0a);
+    cartridge.write(0x4000, 0x10);
+    for size in [0, 100, 128 * 112 * 2] {
+        let camera = cartridge.camera_mut().unwrap();
+        camera.set_source(Box::new(Sized(size)));
+        cartridge.write_ram(0xa000, 0x01);
+        assert!(cartridge.take_dirty(), "capture of {} pixels", size);
+        // Long enough for any capture to finish
+        cartridge.step(CLOCK_SPEED);
+    }
+}
@@ -0,0 +1,61 @@
+// ROMs and cartridges for the tests, blank apart from a valid header
+#![allow(dead_code)]
+
+use missingnogmb::cartridge::Cartridge;
+use missingnogmb::rom_info::{RomInfo, NINTENDO_LOGO};
+
+// A ROM of `rom_size` bytes for a cartridge of the given type with
+// `ram_size` bytes of RAM. It starts by jumping over the header to 0x150.
+pub fn rom(cartridge_type: u8, rom_size: usize, ram_size: usize) -> Vec<u8> {
+    let mut rom = vec![0; rom_size];
+    rom[0x100..0x104].copy_from_slice(&[0x00, 0xc3, 0x50, 0x01]);
+    rom[0x104..0x134].copy_from_slice(&NINTENDO_LOGO);
+    rom[0x147] = cartridge_type;
+    // Sizes that don't fit a header code, like truncated dumps, are left
+    // as 32KiB
+    rom[0x148] = (0..=8).find(|code| 0x8000 << code == rom_size).unwrap_or(0);
+    rom[0x149] = match ram_size {
+        0 => 0x00,
+        0x800 => 0x01,
+        0x2000 => 0x02,
+        0x8000 => 0x03,
+        0x20000 => 0x04,
+        0x10000 => 0x05,
+        _ => panic!("no header code for {} bytes of RAM", ram_size),
+    };
+    set_header_checksum(&mut rom);
+    rom
+}
+
+pub fn set_header_checksum(rom: &mut [u8]) {
+    rom[0x14d] = rom[0x134..0x14d]
+        .iter()
+        .fold(0u8, |x, byte| x.wrapping_sub(*byte).wrapping_sub(1));
+}
+
+pub fn cartridge(cartridge_type: u8, rom_size: usize, ram_size: usize) -> Cartridge {
+    let rom = rom(cartridge_type, rom_size, ram_size);
+    let info = RomInfo::new(&rom).unwrap();
+    Cartridge::new(rom, &info)
+}
+
+// Like `rom`, but with each 16KiB bank starting with its number as a 16-bit
+// little endian value, so tests can tell which bank is mapped where
+pub fn numbered_rom(cartridge_type: u8, rom_size: usize, ram_size: usize) -> Vec<u8> {
+    let mut rom = rom(cartridge_type, rom_size, ram_size);
+    for (bank, data) in rom.chunks_mut(0x4000).enumerate() {
+        data[..2].copy_from_slice(&(bank as u16).to_le_bytes());
+    }
+    rom
+}
+
+pub fn numbered_cartridge(cartridge_type: u8, rom_size: usize, ram_size: usize) -> Cartridge {
+    let rom = numbered_rom(cartridge_type, rom_size, ram_size);
+    let info = RomInfo::new(&rom).unwrap();
+    Cartridge::new(rom, &info)
+}
+
+// The number of the bank mapped at `address`, either 0x0000 or 0x4000
+pub fn mapped_bank(cartridge: &Cartridge, address: u16) -> u16 {
+    u16::from_le_bytes([cartridge.read(address), cartridge.read(address + 1)])
+}
@@ -0,0 +1,227 @@
+// Runs short programs on flat RAM, checking what the CPU does on the bus
+
+use missingnogmb::bus::{Access, Bus, RamBus, Recorder};
+use missingnogmb::cpu::{Cpu, Cycles, Interrupts, Registers};
+
+// Flat RAM with IF and IE at their usual addresses, so interrupts can be
+// raised and enabled by writing to them
+struct InterruptBus(RamBus);
+
+impl Bus for InterruptBus {
+    fn read(&mut self, address: u16) -> u8 {
+        self.0.read(address)
+    }
+
+    fn write(&mut self, address: u16, val: u8) {
+        self.0.write(address, val)
+    }
+
+    fn tick(&mut self) {}
+
+    fn peek(&self, address: u16) -> u8 {
+        self.0.peek(address)
+    }
+
+    fn pending_interrupts(&self) -> Interrupts {
+        Interrupts::from_bits_retain(self.peek(0xff0f) & self.peek(0xffff))
+            .intersection(Interrupts::SOURCES)
+    }
+
+    fn reset_interrupt_flag(&mut self, interrupt: Interrupts) {
+        let flags = self.peek(0xff0f) & !interrupt.bits();
+        self.write(0xff0f, flags)
+    }
+
+    fn reset_div(&mut self) {}
+}
+
+// A CPU about to run `program` from 0x0200, with the stack at 0xd000
+fn setup(program: &[u8]) -> (Cpu, Recorder<InterruptBus>) {
+    let mut ram = RamBus::new();
+    ram.load(0x0200, program);
+
+    let mut cpu = Cpu::new(0);
+    cpu.set_registers(Registers {
+        a: 0,
+        f: 0,
+        b: 0,
+        c: 0,
+        d: 0,
+        e: 0,
+        h: 0,
+        l: 0,
+        sp: 0xd000,
+        pc: 0x0200,
+    });
+    (cpu, Recorder::new(InterruptBus(ram)))
+}
+
+#[test]
+fn illegal_opcode_locks_up() {
+    let (mut cpu, mut bus) = setup(&[0xd3, 0x00]);
+    cpu.step(&mut bus);
+    assert!(cpu.locked());
+    assert_eq!(cpu.registers().pc, 0x0201);
+
+    // Nothing more is fetched, and not even an interrupt gets it going again
+    cpu.set_ime(true);
+    bus.bus.write(0xff0f, 0x01);
+    bus.bus.write(0xffff, 0x01);
+    bus.accesses.clear();
+    for _ in 0..10 {
+        assert_eq!(cpu.step(&mut bus), Cycles(4));
+    }
+    assert_eq!(bus.accesses, [Access::Internal; 10]);
+    assert_eq!(cpu.registers().pc, 0x0201);
+}
+
+#[test]
+fn interrupts_dispatch_in_priority_order() {
+    let (mut cpu, mut bus) = setup(&[0x00]);
+    cpu.set_ime(true);
+    bus.bus.write(0xffff, 0x1f);
+    bus.bus.write(0xff0f, 0x1c);
+
+    // Timer beats serial and joypad, and only its flag is cleared
+    assert_eq!(cpu.step(&mut bus), Cycles(20));
+    assert_eq!(cpu.registers().pc, 0x0050);
+    assert_eq!(bus.bus.peek(0xff0f), 0x18);
+    assert!(!cpu.ime());
+    assert_eq!(
+        bus.accesses,
+        [
+            Access::Internal,
+            Access::Internal,
+            Access::Write(0xcfff, 0x02),
+            Access::Write(0xcffe, 0x00),
+            Access::Internal,
+        ]
+    );
+
+    // Then serial, then joypad
+    cpu.set_ime(true);
+    cpu.step(&mut bus);
+    assert_eq!(cpu.registers().pc, 0x0058);
+    cpu.set_ime(true);
+    cpu.step(&mut bus);
+    assert_eq!(cpu.registers().pc, 0x0060);
+    assert_eq!(bus.bus.peek(0xff0f), 0x00);
+}
+
+#[test]
+fn pushing_over_ie_cancels_dispatch() {
+    let (mut cpu, mut bus) = setup(&[0x00]);
+    // The high byte of PC lands on IE, disabling the pending VBlank
+    cpu.set_registers(Registers {
+        sp: 0x0000,
+        ..cpu.registers()
+    });
+    cpu.set_ime(true);
+    bus.bus.write(0xffff, 0x01);
+    bus.bus.write(0xff0f, 0x01);
+
+    assert_eq!(cpu.step(&mut bus), Cycles(20));
+    assert_eq!(cpu.registers().pc, 0x0000);
+    assert_eq!(cpu.registers().sp, 0xfffe);
+    assert_eq!(bus.bus.peek(0xffff), 0x02);
+    // The flag is left set, as nothing was serviced
+    assert_eq!(bus.bus.peek(0xff0f), 0x01);
+    assert!(!cpu.ime());
+}
+
+#[test]
+fn ei_takes_effect_after_the_next_instruction() {
+    // EI, NOP, NOP
+    let (mut cpu, mut bus) = setup(&[0xfb, 0x00, 0x00]);
+    bus.bus.write(0xffff, 0x01);
+    bus.bus.write(0xff0f, 0x01);
+
+    cpu.step(&mut bus);
+    assert!(!cpu.ime());
+    assert!(cpu.ime_scheduled());
+    cpu.step(&mut bus);
+    assert!(cpu.ime());
+    assert_eq!(cpu.registers().pc, 0x0202);
+
+    // The interrupt is taken before the second NOP
+    cpu.step(&mut bus);
+    assert_eq!(cpu.registers().pc, 0x0040);
+}
+
+#[test]
+fn di_straight_after_ei_cancels_it() {
+    // EI, DI, NOP
+    let (mut cpu, mut bus) = setup(&[0xfb, 0xf3, 0x00]);
+    bus.bus.write(0xffff, 0x01);
+    bus.bus.write(0xff0f, 0x01);
+
+    for _ in 0..3 {
+        cpu.step(&mut bus);
+    }
+    assert!(!cpu.ime());
+    assert!(!cpu.ime_scheduled());
+    assert_eq!(cpu.registers().pc, 0x0203);
+}
+
+#[test]
+fn halt_bug_fetches_the_next_byte_twice() {
+    // HALT, INC A, with an interrupt pending but disabled
+    let (mut cpu, mut bus) = setup(&[0x76, 0x3c, 0x00]);
+    bus.bus.write(0xffff, 0x01);
+    bus.bus.write(0xff0f, 0x01);
+
+    cpu.step(&mut bus);
+    bus.accesses.clear();
+    cpu.step(&mut bus);
+    cpu.step(&mut bus);
+
+    assert_eq!(cpu.registers().a, 2);
+    assert_eq!(cpu.registers().pc, 0x0202);
+    assert_eq!(
+        bus.accesses,
+        [Access::Read(0x0201, 0x3c), Access::Read(0x0201, 0x3c)]
+    );
+}
+
+#[test]
+fn call_pushes_after_an_internal_cycle() {
+    // CALL 0x1234
+    let (mut cpu, mut bus) = setup(&[0xcd, 0x34, 0x12]);
+
+    assert_eq!(cpu.step(&mut bus), Cycles(24));
+    assert_eq!(cpu.registers().pc, 0x1234);
+    assert_eq!(
+        bus.accesses,
+        [
+            Access::Read(0x0200, 0xcd),
+            Access::Read(0x0201, 0x34),
+            Access::Read(0x0202, 0x12),
+            Access::Internal,
+            Access::Write(0xcfff, 0x02),
+            Access::Write(0xcffe, 0x03),
+        ]
+    );
+}
+
+#[test]
+fn push_writes_the_high_byte_first() {
+    // PUSH BC
+    let (mut cpu, mut bus) = setup(&[0xc5]);
+    cpu.set_registers(Registers {
+        b: 0x12,
+        c: 0x34,
+        ..cpu.registers()
+    });
+
+    assert_eq!(cpu.step(&mut bus), Cycles(16));
+    assert_eq!(cpu.registers().sp, 0xcffe);
+    assert_eq!(
+        bus.accesses,
+        [
+            Access::Read(0x0200, 0xc5),
+            Access::Internal,
+            Access::Write(0xcfff, 0x12),
+            Access::Write(0xcffe, 0x34),
+        ]
+    );
+}
@@ -0,0 +1,64 @@
+// Checks instruction decoding and the listing's labels and ranges
+
+use missingnogmb::disassembler::{disassemble, listing};
+
+fn text(bytes: &[u8]) -> String {
+    disassemble(0x0200, |address| bytes[(address - 0x0200) as usize]).text
+}
+
+#[test]
+fn prefixed_instructions() {
+    assert_eq!(text(&[0xcb, 0x7c]), "bit 7,h");
+    assert_eq!(text(&[0xcb, 0x16]), "rl (hl)");
+    assert_eq!(disassemble(0, |_| 0xcb).bytes.len(), 2);
+}
+
+#[test]
+fn immediates() {
+    assert_eq!(text(&[0x3e, 0x12]), "ld a,$12");
+    assert_eq!(text(&[0xfa, 0x34, 0x12]), "ld a,($1234)");
+    assert_eq!(text(&[0xe0, 0x44]), "ldh ($ff44),a");
+    assert_eq!(text(&[0xf8, 0xfe]), "ld hl,sp-$02");
+    // Relative jumps are from the end of the instruction
+    assert_eq!(text(&[0x18, 0xfe]), "jr $0200");
+    assert_eq!(text(&[0x20, 0x10]), "jr nz,$0212");
+}
+
+#[test]
+fn range_ending_mid_instruction_shows_it_whole() {
+    let mut rom = vec![0; 0x200];
+    rom[0x0150..0x0153].copy_from_slice(&[0xc3, 0x50, 0x01]);
+
+    assert_eq!(
+        listing(0x0150, 0x0151, |address| rom[address as usize]),
+        "    0150: c3 50 01     jp $0150\n"
+    );
+}
+
+#[test]
+fn vectors_are_labelled() {
+    let mut rom = vec![0; 0x200];
+    rom[0x0038..0x003b].copy_from_slice(&[0xc3, 0x50, 0x01]);
+
+    assert_eq!(
+        listing(0x0037, 0x003b, |address| rom[address as usize]),
+        "    0037: 00           nop\n\
+         rst_38:\n    \
+         0038: c3 50 01     jp $0150\n    \
+         003b: 00           nop\n"
+    );
+}
+
+#[test]
+fn vectors_are_labelled_inside_data() {
+    // A JP whose operand would run over the vblank vector
+    let mut rom = vec![0; 0x200];
+    rom[0x003e..0x0040].copy_from_slice(&[0xc3, 0x12]);
+
+    assert_eq!(
+        listing(0x003e, 0x0040, |address| rom[address as usize]),
+        "    003e:              db $c3,$12\n\
+         vblank:\n    \
+         0040: 00           nop\n"
+    );
+}
@@ -0,0 +1,72 @@
+// Checks that broken ROM files are turned away or survive being run, rather
+// than panicking
+
+mod common;
+
+use missingnogmb::cpu::Cycles;
+use missingnogmb::gameboy::Gameboy;
+use missingnogmb::rom_info::{HeaderError, RomInfo};
+use missingnogmb::test_runner::{self, Outcome};
+
+// A ROM with a valid header, running `code` from 0x150
+fn rom(size: usize, cartridge_type: u8, code: &[u8]) -> Vec<u8> {
+    let mut rom = common::rom(cartridge_type, size, 0);
+    rom[0x150..0x150 + code.len()].copy_from_slice(code);
+    rom
+}
+
+#[test]
+fn truncated_rom_runs() {
+    // LD A,(0x4000), well past the end of the file, then loop forever
+    let rom = rom(0x200, 0x00, &[0xfa, 0x00, 0x40, 0x18, 0xfe]);
+    let result = test_runner::run(rom, Cycles(100_000)).unwrap();
+    assert_eq!(result.outcome, Outcome::TimedOut);
+}
+
+#[test]
+fn broken_headers_are_rejected() {
+    assert_eq!(
+        RomInfo::new(&[0; 0x100]).err(),
+        Some(HeaderError::TooSmall(0x100))
+    );
+
+    let mut no_logo = rom(0x8000, 0x00, &[]);
+    no_logo[0x104] ^= 0xff;
+    assert_eq!(RomInfo::new(&no_logo).err(), Some(HeaderError::BadLogo));
+
+    assert_eq!(
+        RomInfo::new(&rom(0x8000, 0x44, &[])).err(),
+        Some(HeaderError::UnknownCartridgeType(0x44))
+    );
+}
+
+#[test]
+fn bad_header_checksum_fails_validation() {
+    let mut rom = rom(0x8000, 0x00, &[]);
+    assert_eq!(RomInfo::new(&rom).unwrap().validate(), Ok(()));
+
+    rom[0x14d] ^= 0xff;
+    let info = RomInfo::new(&rom).unwrap();
+    assert_eq!(
+        info.validate(),
+        Err(HeaderError::BadHeaderChecksum {
+            expected: rom[0x14d],
+            computed: rom[0x14d] ^ 0xff,
+        })
+    );
+}
+
+#[test]
+fn bad_header_checksum_needs_an_override_to_boot() {
+    let mut rom = rom(0x8000, 0x00, &[]);
+    rom[0x14d] ^= 0xff;
+
+    assert_eq!(
+        Gameboy::new(rom.clone()).err(),
+        Some(HeaderError::BadHeaderChecksum {
+            expected: rom[0x14d],
+            computed: rom[0x14d] ^ 0xff,
+        })
+    );
+    assert!(Gameboy::new_unchecked(rom).is_ok());
+}
@@ -0,0 +1,51 @@
+// Checks HuC1's infrared port, which replaces RAM at 0xa000-0xbfff while
+// selected
+
+mod common;
+
+use missingnogmb::cartridge::Cartridge;
+
+fn cartridge() -> Cartridge {
+    // HuC1 with RAM and a battery
+    common::cartridge(0xff, 0x8000, 0x8000)
+}
+
+#[test]
+fn infrared_mode_drives_the_led() {
+    let mut cartridge = cartridge();
+    cartridge.write(0x0000, 0x0e);
+
+    cartridge.write_ram(0xa000, 0x01);
+    assert!(cartridge.infrared_led());
+    cartridge.write_ram(0xa000, 0x00);
+    assert!(!cartridge.infrared_led());
+    // Nothing was written to RAM
+    assert!(!cartridge.take_dirty());
+}
+
+#[test]
+fn infrared_mode_reads_the_receiver() {
+    let mut cartridge = cartridge();
+    cartridge.write(0x0000, 0x0e);
+
+    assert_eq!(cartridge.read_ram(0xa000), 0xc0);
+    cartridge.receive_infrared(true);
+    assert_eq!(cartridge.read_ram(0xa000), 0xc1);
+    cartridge.receive_infrared(false);
+    assert_eq!(cartridge.read_ram(0xbfff), 0xc0);
+}
+
+#[test]
+fn leaving_infrared_mode_maps_ram_back() {
+    let mut cartridge = cartridge();
+    cartridge.write(0x4000, 0x01);
+    cartridge.write_ram(0xa000, 0x42);
+    assert!(cartridge.take_dirty());
+
+    cartridge.write(0x0000, 0x0e);
+    assert_eq!(cartridge.read_ram(0xa000), 0xc0);
+
+    cartridge.write(0x0000, 0x00);
+    assert_eq!(cartridge.read_ram(0xa000), 0x42);
+    assert_eq!(cartridge.battery_ram().unwrap()[0x2000], 0x42);
+}
@@ -0,0 +1,29 @@
+// Checks that pressing a button wakes the CPU from STOP
+
+mod common;
+
+use missingnogmb::gameboy::Gameboy;
+use missingnogmb::joypad::Button;
+
+// STOP, then INC B forever
+fn rom() -> Vec<u8> {
+    let mut rom = common::rom(0x00, 0x8000, 0);
+    rom[0x150..0x155].copy_from_slice(&[0x10, 0x00, 0x04, 0x18, 0xfd]);
+    rom
+}
+
+#[test]
+fn button_wakes_from_stop() {
+    let mut gb = Gameboy::new(rom()).unwrap();
+    for _ in 0..100 {
+        gb.step();
+    }
+    assert_eq!(gb.registers().pc, 0x152);
+    assert_eq!(gb.registers().b, 0);
+
+    gb.set_button(Button::Start, true);
+    for _ in 0..100 {
+        gb.step();
+    }
+    assert_ne!(gb.registers().b, 0);
+}
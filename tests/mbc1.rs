@@ -0,0 +1,115 @@
+// Checks which ROM and RAM banks MBC1 maps for each register setting
+
+mod common;
+
+use common::{mapped_bank, numbered_cartridge};
+use missingnogmb::cartridge::Cartridge;
+use missingnogmb::rom_info::{MbcType, RomInfo, NINTENDO_LOGO};
+
+// MBC1 with RAM and a battery
+const MBC1: u8 = 0x03;
+
+#[test]
+fn bank_0_selects_bank_1() {
+    let mut cartridge = numbered_cartridge(MBC1, 0x200000, 0x8000);
+
+    for bank2 in 0..4 {
+        cartridge.write(0x4000, bank2);
+        // Only the low 5 bits are checked, so 0x20, 0x40 and 0x60 can't be
+        // mapped either
+        cartridge.write(0x2000, 0x00);
+        assert_eq!(mapped_bank(&cartridge, 0x4000), (bank2 as u16) << 5 | 1);
+        cartridge.write(0x2000, 0x20);
+        assert_eq!(mapped_bank(&cartridge, 0x4000), (bank2 as u16) << 5 | 1);
+    }
+
+    cartridge.write(0x4000, 0x01);
+    cartridge.write(0x2000, 0x05);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 0x25);
+    assert_eq!(mapped_bank(&cartridge, 0x0000), 0x00);
+}
+
+#[test]
+fn mode_1_banks_rom_0_and_ram() {
+    let mut cartridge = numbered_cartridge(MBC1, 0x200000, 0x8000);
+    cartridge.write(0x0000, 0x0a);
+
+    // Mode 0 always has RAM bank 0
+    cartridge.write(0x4000, 0x02);
+    cartridge.write_ram(0xa000, 0x12);
+    cartridge.write(0x6000, 0x01);
+    cartridge.write_ram(0xa000, 0x34);
+
+    assert_eq!(mapped_bank(&cartridge, 0x0000), 0x40);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 0x41);
+    assert_eq!(cartridge.read_ram(0xa000), 0x34);
+
+    cartridge.write(0x6000, 0x00);
+    assert_eq!(mapped_bank(&cartridge, 0x0000), 0x00);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 0x41);
+    assert_eq!(cartridge.read_ram(0xa000), 0x12);
+
+    let ram = cartridge.battery_ram().unwrap();
+    assert_eq!((ram[0x0000], ram[0x4000]), (0x12, 0x34));
+}
+
+#[test]
+fn small_roms_and_ram_wrap_around() {
+    // Four banks of ROM and a single bank of RAM
+    let mut cartridge = numbered_cartridge(MBC1, 0x10000, 0x2000);
+    cartridge.write(0x0000, 0x0a);
+
+    cartridge.write(0x2000, 0x06);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 0x02);
+    cartridge.write(0x2000, 0x04);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 0x00);
+
+    cartridge.write(0x6000, 0x01);
+    cartridge.write(0x4000, 0x01);
+    assert_eq!(mapped_bank(&cartridge, 0x0000), 0x00);
+
+    cartridge.write_ram(0xa000, 0x56);
+    cartridge.write(0x4000, 0x02);
+    assert_eq!(cartridge.read_ram(0xa000), 0x56);
+}
+
+// A 1MiB multicart with a game header at the start of every 256KiB
+fn multicart_rom() -> Vec<u8> {
+    let mut rom = common::numbered_rom(MBC1, 0x100000, 0);
+    for base in (0x40000..rom.len()).step_by(0x40000) {
+        rom[base + 0x104..base + 0x134].copy_from_slice(&NINTENDO_LOGO);
+    }
+    rom
+}
+
+#[test]
+fn multicarts_are_detected_by_their_logos() {
+    let info = RomInfo::new(&multicart_rom()).unwrap();
+    assert_eq!(info.mbc_type, MbcType::MBC1M);
+
+    let single = common::numbered_rom(MBC1, 0x100000, 0);
+    assert_eq!(RomInfo::new(&single).unwrap().mbc_type, MbcType::MBC1);
+}
+
+#[test]
+fn multicarts_give_each_game_16_banks() {
+    let rom = multicart_rom();
+    let info = RomInfo::new(&rom).unwrap();
+    let mut cartridge = Cartridge::new(rom, &info);
+
+    cartridge.write(0x4000, 0x02);
+    // Bit 4 of the low bank isn't connected
+    cartridge.write(0x2000, 0x13);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 0x23);
+    assert_eq!(mapped_bank(&cartridge, 0x0000), 0x00);
+
+    // Mode 1 maps each game's first bank at 0x0000
+    cartridge.write(0x6000, 0x01);
+    cartridge.write(0x4000, 0x03);
+    assert_eq!(mapped_bank(&cartridge, 0x0000), 0x30);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 0x33);
+
+    // A low bank of 0x10 still isn't 0, so isn't bumped up to 1
+    cartridge.write(0x2000, 0x10);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 0x30);
+}
@@ -0,0 +1,58 @@
+// Checks MBC2's register decoding and its built-in half-byte RAM
+
+mod common;
+
+use common::{mapped_bank, numbered_cartridge};
+
+// MBC2 with a battery. Its RAM is built in, so the header gives none.
+const MBC2: u8 = 0x06;
+
+#[test]
+fn address_bit_8_picks_the_register() {
+    let mut cartridge = numbered_cartridge(MBC2, 0x40000, 0);
+
+    // Bit 8 clear enables RAM, whatever the rest of the address
+    cartridge.write(0x3eff, 0x0a);
+    cartridge.write_ram(0xa000, 0x05);
+    assert_eq!(cartridge.read_ram(0xa000), 0xf5);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 0x01);
+
+    // Bit 8 set selects the ROM bank, of which there are 16
+    cartridge.write(0x0100, 0x03);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 0x03);
+    cartridge.write(0x2100, 0x1f);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 0x0f);
+    cartridge.write(0x3fff, 0x00);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 0x01);
+    assert_eq!(cartridge.read_ram(0xa000), 0xf5);
+
+    cartridge.write(0x0000, 0x00);
+    assert_eq!(cartridge.read_ram(0xa000), 0xff);
+}
+
+#[test]
+fn ram_holds_half_bytes() {
+    let mut cartridge = numbered_cartridge(MBC2, 0x40000, 0);
+    cartridge.write(0x0000, 0x0a);
+
+    // Only the low nibble is stored, and the top one reads as set
+    cartridge.write_ram(0xa123, 0xa5);
+    assert_eq!(cartridge.read_ram(0xa123), 0xf5);
+    assert_eq!(cartridge.battery_ram().unwrap()[0x123], 0x05);
+    assert!(cartridge.take_dirty());
+}
+
+#[test]
+fn ram_is_mirrored_every_512_bytes() {
+    let mut cartridge = numbered_cartridge(MBC2, 0x40000, 0);
+    cartridge.write(0x0000, 0x0a);
+
+    cartridge.write_ram(0xa010, 0x07);
+    for mirror in (0xa010..0xc000).step_by(0x200) {
+        assert_eq!(cartridge.read_ram(mirror), 0xf7);
+    }
+
+    cartridge.write_ram(0xbe10, 0x08);
+    assert_eq!(cartridge.read_ram(0xa010), 0xf8);
+    assert_eq!(cartridge.battery_ram().unwrap().len(), 0x200);
+}
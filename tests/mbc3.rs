@@ -0,0 +1,130 @@
+// Drives the MBC3's real time clock through its registers, the way games do
+
+mod common;
+
+use missingnogmb::cartridge::Cartridge;
+use missingnogmb::cpu::CLOCK_SPEED;
+
+const SECONDS: u8 = 0x08;
+const MINUTES: u8 = 0x09;
+const HOURS: u8 = 0x0a;
+const DAYS_LOW: u8 = 0x0b;
+const DAYS_HIGH: u8 = 0x0c;
+
+fn cartridge() -> Cartridge {
+    // MBC3 with a clock, RAM and a battery
+    let mut cartridge = common::cartridge(0x10, 0x8000, 0x8000);
+    cartridge.write(0x0000, 0x0a);
+    cartridge
+}
+
+fn set(cartridge: &mut Cartridge, register: u8, val: u8) {
+    cartridge.write(0x4000, register);
+    cartridge.write_ram(0xa000, val);
+}
+
+fn get(cartridge: &mut Cartridge, register: u8) -> u8 {
+    cartridge.write(0x4000, register);
+    cartridge.read_ram(0xa000)
+}
+
+fn latch(cartridge: &mut Cartridge) {
+    cartridge.write(0x6000, 0x00);
+    cartridge.write(0x6000, 0x01);
+}
+
+fn seconds(cartridge: &mut Cartridge, seconds: u32) {
+    for _ in 0..seconds {
+        cartridge.step(CLOCK_SPEED);
+    }
+}
+
+#[test]
+fn reads_are_frozen_until_latched() {
+    let mut cartridge = cartridge();
+    set(&mut cartridge, SECONDS, 5);
+    seconds(&mut cartridge, 1);
+    assert_eq!(get(&mut cartridge, SECONDS) & 0x3f, 0);
+
+    latch(&mut cartridge);
+    seconds(&mut cartridge, 1);
+    assert_eq!(get(&mut cartridge, SECONDS) & 0x3f, 6);
+
+    // Only a 0x00 then 0x01 latches
+    cartridge.write(0x6000, 0x01);
+    assert_eq!(get(&mut cartridge, SECONDS) & 0x3f, 6);
+    cartridge.write(0x6000, 0x02);
+    cartridge.write(0x6000, 0x01);
+    assert_eq!(get(&mut cartridge, SECONDS) & 0x3f, 6);
+
+    latch(&mut cartridge);
+    assert_eq!(get(&mut cartridge, SECONDS) & 0x3f, 7);
+}
+
+#[test]
+fn halt_stops_the_clock() {
+    let mut cartridge = cartridge();
+    set(&mut cartridge, SECONDS, 10);
+    set(&mut cartridge, DAYS_HIGH, 0x40);
+    seconds(&mut cartridge, 3);
+
+    latch(&mut cartridge);
+    assert_eq!(get(&mut cartridge, SECONDS) & 0x3f, 10);
+    assert_eq!(get(&mut cartridge, DAYS_HIGH) & 0x40, 0x40);
+
+    set(&mut cartridge, DAYS_HIGH, 0x00);
+    seconds(&mut cartridge, 3);
+    latch(&mut cartridge);
+    assert_eq!(get(&mut cartridge, SECONDS) & 0x3f, 13);
+}
+
+#[test]
+fn day_overflow_sets_carry() {
+    let mut cartridge = cartridge();
+    set(&mut cartridge, SECONDS, 59);
+    set(&mut cartridge, MINUTES, 59);
+    set(&mut cartridge, HOURS, 23);
+    set(&mut cartridge, DAYS_LOW, 0xff);
+    set(&mut cartridge, DAYS_HIGH, 0x01);
+    seconds(&mut cartridge, 1);
+
+    latch(&mut cartridge);
+    assert_eq!(get(&mut cartridge, SECONDS) & 0x3f, 0);
+    assert_eq!(get(&mut cartridge, MINUTES) & 0x3f, 0);
+    assert_eq!(get(&mut cartridge, HOURS) & 0x1f, 0);
+    assert_eq!(get(&mut cartridge, DAYS_LOW), 0);
+    assert_eq!(get(&mut cartridge, DAYS_HIGH) & 0xc1, 0x80);
+
+    // The carry stays set until the game clears it
+    seconds(&mut cartridge, 1);
+    latch(&mut cartridge);
+    assert_eq!(get(&mut cartridge, DAYS_HIGH) & 0xc1, 0x80);
+    set(&mut cartridge, DAYS_HIGH, 0x00);
+    latch(&mut cartridge);
+    assert_eq!(get(&mut cartridge, DAYS_HIGH) & 0xc1, 0x00);
+}
+
+#[test]
+fn out_of_range_values_wrap_without_carrying() {
+    let mut cartridge = cartridge();
+    set(&mut cartridge, SECONDS, 0x3f);
+    set(&mut cartridge, MINUTES, 0x3f);
+    set(&mut cartridge, HOURS, 0x1f);
+    seconds(&mut cartridge, 1);
+
+    latch(&mut cartridge);
+    assert_eq!(get(&mut cartridge, SECONDS) & 0x3f, 0);
+    assert_eq!(get(&mut cartridge, MINUTES) & 0x3f, 0x3f);
+    assert_eq!(get(&mut cartridge, HOURS) & 0x1f, 0x1f);
+
+    // In range minutes carry into the hours, which wrap without carrying into
+    // the days
+    set(&mut cartridge, SECONDS, 59);
+    set(&mut cartridge, MINUTES, 59);
+    seconds(&mut cartridge, 1);
+    latch(&mut cartridge);
+    assert_eq!(get(&mut cartridge, SECONDS) & 0x3f, 0);
+    assert_eq!(get(&mut cartridge, MINUTES) & 0x3f, 0);
+    assert_eq!(get(&mut cartridge, HOURS) & 0x1f, 0);
+    assert_eq!(get(&mut cartridge, DAYS_LOW), 0);
+}
@@ -0,0 +1,68 @@
+// Checks MBC5's 9-bit ROM banking and the rumble motor on bit 3 of its RAM
+// bank register
+
+mod common;
+
+use common::{mapped_bank, numbered_cartridge};
+use missingnogmb::gameboy::Gameboy;
+
+// MBC5 with RAM and a battery
+const MBC5: u8 = 0x1b;
+// MBC5 with a rumble motor, RAM and a battery
+const MBC5_RUMBLE: u8 = 0x1e;
+
+#[test]
+fn rom_bank_has_9_bits() {
+    let mut cartridge = numbered_cartridge(MBC5, 0x800000, 0);
+
+    cartridge.write(0x2000, 0x45);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 0x045);
+    cartridge.write(0x3000, 0x01);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 0x145);
+    // Only bit 0 of the upper register counts
+    cartridge.write(0x3000, 0xfe);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 0x045);
+}
+
+#[test]
+fn bank_0_can_be_mapped_high() {
+    let mut cartridge = numbered_cartridge(MBC5, 0x800000, 0);
+
+    cartridge.write(0x2000, 0x00);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 0x000);
+    cartridge.write(0x3000, 0x01);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 0x100);
+    assert_eq!(mapped_bank(&cartridge, 0x0000), 0x000);
+}
+
+#[test]
+fn rumble_takes_bit_3_from_the_ram_bank() {
+    let mut cartridge = numbered_cartridge(MBC5_RUMBLE, 0x8000, 0x20000);
+    cartridge.write(0x0000, 0x0a);
+
+    cartridge.write(0x4000, 0x09);
+    assert!(cartridge.rumbling());
+    cartridge.write_ram(0xa000, 0x12);
+
+    cartridge.write(0x4000, 0x01);
+    assert!(!cartridge.rumbling());
+    assert_eq!(cartridge.read_ram(0xa000), 0x12);
+    assert_eq!(cartridge.battery_ram().unwrap()[0x2000], 0x12);
+}
+
+#[test]
+fn rumble_reaches_the_frontend() {
+    // Switch the motor on then off, then halt
+    let mut rom = common::rom(MBC5_RUMBLE, 0x8000, 0x2000);
+    rom[0x150..0x15b].copy_from_slice(&[
+        0x3e, 0x08, 0xea, 0x00, 0x40, 0x3e, 0x00, 0xea, 0x00, 0x40, 0x76,
+    ]);
+    let mut gb = Gameboy::new(rom).unwrap();
+
+    let mut events = Vec::new();
+    for _ in 0..20 {
+        gb.step();
+        events.extend(gb.take_rumble_event());
+    }
+    assert_eq!(events, [true, false]);
+}
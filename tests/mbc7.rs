@@ -0,0 +1,120 @@
+// Drives the MBC7's EEPROM a bit at a time through register 0x8, the way
+// games do
+
+mod common;
+
+use missingnogmb::cartridge::Cartridge;
+
+const EEPROM: u16 = 0xa080;
+
+// Opcodes in the top two bits of the 10-bit command, with the rest of the
+// opcode 0 commands picked by the top of the address
+const READ: u16 = 0b10 << 8;
+const WRITE: u16 = 0b01 << 8;
+const ERASE: u16 = 0b11 << 8;
+const EWDS: u16 = 0b0000 << 6;
+const WRAL: u16 = 0b0001 << 6;
+const ERAL: u16 = 0b0010 << 6;
+const EWEN: u16 = 0b0011 << 6;
+
+fn cartridge() -> Cartridge {
+    let mut cartridge = common::cartridge(0x22, 0x8000, 0);
+    cartridge.write(0x0000, 0x0a);
+    cartridge.write(0x4000, 0x40);
+    cartridge
+}
+
+// Clocks a bit in with chip select held, returning DO after the rising edge
+fn clock(cartridge: &mut Cartridge, bit: bool) -> bool {
+    let data_in = (bit as u8) << 1;
+    cartridge.write_ram(EEPROM, 0x80 | data_in);
+    cartridge.write_ram(EEPROM, 0xc0 | data_in);
+    cartridge.read_ram(EEPROM) & 0x01 != 0
+}
+
+// Clocks in the low `count` bits, highest first, returning DO after the last
+fn send(cartridge: &mut Cartridge, bits: u16, count: u8) -> bool {
+    let mut data_out = false;
+    for i in (0..count).rev() {
+        data_out = clock(cartridge, bits >> i & 1 != 0);
+    }
+    data_out
+}
+
+// Selects the chip and sends the start bit and a command
+fn command(cartridge: &mut Cartridge, command: u16) -> bool {
+    cartridge.write_ram(EEPROM, 0x00);
+    cartridge.write_ram(EEPROM, 0x80);
+    clock(cartridge, true);
+    send(cartridge, command, 10)
+}
+
+fn send_word(cartridge: &mut Cartridge, word: u16) -> bool {
+    send(cartridge, word, 16)
+}
+
+fn receive_word(cartridge: &mut Cartridge) -> u16 {
+    (0..16).fold(0, |word, _| word << 1 | clock(cartridge, false) as u16)
+}
+
+fn word(cartridge: &Cartridge, address: usize) -> u16 {
+    let ram = cartridge.battery_ram().unwrap();
+    u16::from_be_bytes([ram[address * 2], ram[address * 2 + 1]])
+}
+
+#[test]
+fn read_starts_with_a_dummy_bit() {
+    let mut cartridge = cartridge();
+    let mut eeprom = vec![0; 0x100];
+    eeprom[6..10].copy_from_slice(&[0xbe, 0xef, 0x12, 0x34]);
+    cartridge.load_battery_ram(&eeprom);
+
+    assert!(!command(&mut cartridge, READ | 3));
+    assert_eq!(receive_word(&mut cartridge), 0xbeef);
+    // Reads carry on into the next word
+    assert_eq!(receive_word(&mut cartridge), 0x1234);
+}
+
+#[test]
+fn writes_need_enabling() {
+    let mut cartridge = cartridge();
+
+    command(&mut cartridge, WRITE | 5);
+    send_word(&mut cartridge, 0x1234);
+    assert_eq!(word(&cartridge, 5), 0x0000);
+    assert!(!cartridge.take_dirty());
+
+    command(&mut cartridge, EWEN);
+    command(&mut cartridge, WRITE | 5);
+    // Ready as soon as the last bit is in
+    assert!(send_word(&mut cartridge, 0x1234));
+    assert_eq!(word(&cartridge, 5), 0x1234);
+    assert!(cartridge.take_dirty());
+
+    command(&mut cartridge, EWDS);
+    command(&mut cartridge, WRITE | 5);
+    send_word(&mut cartridge, 0x5678);
+    command(&mut cartridge, ERASE | 5);
+    command(&mut cartridge, ERAL);
+    assert_eq!(word(&cartridge, 5), 0x1234);
+    assert!(!cartridge.take_dirty());
+}
+
+#[test]
+fn write_and_erase_all() {
+    let mut cartridge = cartridge();
+    command(&mut cartridge, EWEN);
+
+    command(&mut cartridge, WRAL);
+    assert!(send_word(&mut cartridge, 0xa55a));
+    assert!((0..0x80).all(|address| word(&cartridge, address) == 0xa55a));
+    assert!(cartridge.take_dirty());
+
+    assert!(command(&mut cartridge, ERASE | 0x7f));
+    assert_eq!(word(&cartridge, 0x7f), 0xffff);
+    assert_eq!(word(&cartridge, 0x7e), 0xa55a);
+
+    assert!(command(&mut cartridge, ERAL));
+    assert!((0..0x80).all(|address| word(&cartridge, address) == 0xffff));
+    assert!(cartridge.take_dirty());
+}
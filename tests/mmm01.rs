@@ -0,0 +1,60 @@
+// Checks that MMM01 collections boot into their menu, and that the menu can
+// hand a game its own slice of the ROM
+
+mod common;
+
+use common::mapped_bank;
+use missingnogmb::cartridge::Cartridge;
+use missingnogmb::rom_info::{MbcType, RomInfo};
+
+// A 256KiB collection. The first game's header is at the start, with the
+// menu's in the last 32KiB.
+fn cartridge() -> Cartridge {
+    let mut rom = common::numbered_rom(0x01, 0x40000, 0);
+    let menu = common::rom(0x0d, 0x40000, 0x2000);
+    let base = rom.len() - 0x8000;
+    rom[base + 0x100..base + 0x150].copy_from_slice(&menu[0x100..0x150]);
+
+    let info = RomInfo::new(&rom).unwrap();
+    assert_eq!(info.mbc_type, MbcType::MMM01);
+    Cartridge::new(rom, &info)
+}
+
+#[test]
+fn boots_into_the_menu() {
+    let mut cartridge = cartridge();
+    assert_eq!(mapped_bank(&cartridge, 0x0000), 14);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 15);
+
+    // Setting up a game doesn't map it in yet
+    cartridge.write(0x2000, 0x04);
+    assert_eq!(mapped_bank(&cartridge, 0x0000), 14);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 15);
+}
+
+#[test]
+fn games_are_locked_to_their_banks() {
+    let mut cartridge = cartridge();
+
+    // A 64KiB game from bank 4, with bits 2-4 of its bank number locked
+    cartridge.write(0x2000, 0x04);
+    cartridge.write(0x6000, 0b1110 << 2);
+    cartridge.write(0x0000, 0x40);
+    assert_eq!(mapped_bank(&cartridge, 0x0000), 4);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 5);
+
+    cartridge.write(0x2000, 0x02);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 6);
+    cartridge.write(0x2000, 0x1f);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 7);
+    // Like MBC1, the game's bank 0 selects its bank 1 instead
+    cartridge.write(0x2000, 0x00);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 5);
+
+    // Once mapped, the game can't get back to the menu
+    cartridge.write(0x0000, 0x00);
+    cartridge.write(0x6000, 0x00);
+    assert_eq!(mapped_bank(&cartridge, 0x0000), 4);
+    cartridge.write(0x2000, 0x03);
+    assert_eq!(mapped_bank(&cartridge, 0x4000), 7);
+}
@@ -0,0 +1,68 @@
+// Runs every ROM from mooneye's test suite found under tests/roms/mooneye/,
+// e.g. tests/roms/mooneye/acceptance/timer/div_write.gb, and prints a table
+// of the results. Run it with `cargo test -- --ignored`.
+
+use missingnogmb::cpu::{Cycles, CLOCK_SPEED};
+use missingnogmb::test_runner::{self, Outcome};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Each ROM finishes within a few emulated seconds when it doesn't hang
+const BUDGET: Cycles = Cycles(10 * CLOCK_SPEED.0);
+
+// These need a human to check the screen
+const SKIPPED_DIRS: [&str; 2] = ["manual-only", "utils"];
+
+fn walk(dir: &Path, roms: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            if !SKIPPED_DIRS.iter().any(|skipped| path.ends_with(skipped)) {
+                walk(&path, roms);
+            }
+        } else if path.extension().is_some_and(|ext| ext == "gb") {
+            roms.push(path);
+        }
+    }
+}
+
+#[test]
+#[ignore = "needs the ROMs in tests/roms/mooneye/"]
+fn mooneye() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/roms/mooneye");
+    assert!(dir.is_dir(), "no ROMs in {}", dir.display());
+
+    let mut roms = Vec::new();
+    walk(&dir, &mut roms);
+    roms.sort();
+    assert!(!roms.is_empty(), "no ROMs in {}", dir.display());
+
+    let mut results = Vec::new();
+    for rom in &roms {
+        let result = test_runner::run_mooneye(fs::read(rom).unwrap(), BUDGET).unwrap();
+        let name = rom.strip_prefix(&dir).unwrap().display().to_string();
+        results.push((name, result.outcome));
+    }
+
+    let width = results
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    for (name, outcome) in &results {
+        println!("{:<width$}  {:?}", name, outcome);
+    }
+
+    let failed = results
+        .iter()
+        .filter(|(_, outcome)| *outcome != Outcome::Passed)
+        .count();
+    println!("{} passed, {} failed", results.len() - failed, failed);
+    assert_eq!(
+        failed,
+        0,
+        "{} of {} mooneye ROMs failed",
+        failed,
+        results.len()
+    );
+}
@@ -0,0 +1,128 @@
+// Round trips of the .sav formats, including the clock trailers other
+// emulators expect
+
+mod common;
+
+use common::cartridge;
+use missingnogmb::mbc::rtc;
+use missingnogmb::save;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+fn save_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("missingnogmb-{}-{}.sav", name, std::process::id()))
+}
+
+#[test]
+fn huc3_clock_round_trip() {
+    let path = save_path("huc3");
+
+    let mut original = cartridge(0xfe, 0x8000, 0x2000);
+    original
+        .huc3_clock_mut()
+        .unwrap()
+        .restore(23 * 60 + 59, 0x123);
+    save::store(&path, &original).unwrap();
+
+    // Pretend the save was made a day and a minute ago
+    let mut data = fs::read(&path).unwrap();
+    assert_eq!(data.len(), 0x2000 + 17);
+    let saved_at = SystemTime::now() - Duration::from_secs(86400 + 60);
+    data[0x2000..0x2008].copy_from_slice(&rtc::to_unix_time(saved_at).to_le_bytes());
+    fs::write(&path, data).unwrap();
+
+    let mut restored = cartridge(0xfe, 0x8000, 0x2000);
+    save::load(&path, &mut restored).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let clock = restored.huc3_clock().unwrap();
+    assert_eq!(clock.minutes(), 0);
+    assert_eq!(clock.days(), 0x125);
+}
+
+#[test]
+fn only_ram_writes_need_saving() {
+    // MBC3 with a clock, battery and 32KiB of RAM
+    let mut cartridge = cartridge(0x10, 0x8000, 0x8000);
+
+    cartridge.write_ram(0xa000, 0x12);
+    assert!(!cartridge.take_dirty(), "write with RAM disabled");
+
+    cartridge.write(0x0000, 0x0a);
+    cartridge.write(0x4000, 0x08);
+    cartridge.write_ram(0xa000, 0x12);
+    assert!(!cartridge.take_dirty(), "write to a clock register");
+
+    cartridge.write(0x4000, 0x00);
+    cartridge.write_ram(0xa000, 0x12);
+    assert!(cartridge.take_dirty());
+}
+
+// A BGB style trailer, each register a 32-bit word
+fn rtc_trailer(registers: [u8; 5], latched: [u8; 5], saved_at: SystemTime, short: bool) -> Vec<u8> {
+    let mut trailer = Vec::new();
+    for val in registers.into_iter().chain(latched) {
+        trailer.extend_from_slice(&(val as u32).to_le_bytes());
+    }
+    let timestamp = rtc::to_unix_time(saved_at).to_le_bytes();
+    trailer.extend_from_slice(if short { &timestamp[..4] } else { &timestamp });
+    trailer
+}
+
+#[test]
+fn rtc_trailer_round_trip() {
+    let path = save_path("rtc");
+
+    // Halted with the carry set, so no time passes while loading
+    let registers = [0x3b, 0x3b, 0x17, 0xff, 0xc1];
+    let latched = [0x01, 0x02, 0x03, 0x04, 0x81];
+    let saved_at = SystemTime::now() - Duration::from_secs(3600);
+    let trailer = rtc_trailer(registers, latched, saved_at, false);
+    assert_eq!(trailer.len(), 48);
+
+    let mut data = vec![0x5a; 0x2000];
+    data.extend_from_slice(&trailer);
+    fs::write(&path, &data).unwrap();
+
+    let mut cartridge = cartridge(0x10, 0x8000, 0x2000);
+    save::load(&path, &mut cartridge).unwrap();
+    let rtc = cartridge.rtc().unwrap();
+    assert_eq!(rtc.registers(), registers);
+    assert_eq!(rtc.latched_registers(), latched);
+
+    save::store(&path, &cartridge).unwrap();
+    let stored = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(stored.len(), data.len());
+    assert_eq!(stored[..0x2000 + 40], data[..0x2000 + 40]);
+}
+
+#[test]
+fn short_rtc_trailer_catches_up() {
+    let path = save_path("short-rtc");
+
+    // Day 0x1ff with the carry set, running, saved 90 seconds ago
+    let registers = [0x00, 0x0a, 0x05, 0xff, 0x81];
+    let latched = [0x00; 5];
+    let saved_at = SystemTime::now() - Duration::from_secs(90);
+    let trailer = rtc_trailer(registers, latched, saved_at, true);
+    assert_eq!(trailer.len(), 44);
+
+    let mut data = vec![0; 0x2000];
+    data.extend_from_slice(&trailer);
+    fs::write(&path, data).unwrap();
+
+    let mut cartridge = cartridge(0x10, 0x8000, 0x2000);
+    save::load(&path, &mut cartridge).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    // The timestamp can be up to a second behind
+    let [seconds, minutes, hours, days_low, days_high] = cartridge.rtc().unwrap().registers();
+    assert!((30..=31).contains(&seconds), "seconds {}", seconds);
+    assert_eq!(
+        (minutes, hours, days_low, days_high),
+        (0x0b, 0x05, 0xff, 0x81)
+    );
+    assert_eq!(cartridge.rtc().unwrap().latched_registers(), latched);
+}
@@ -0,0 +1,151 @@
+// Runs the sm83 SingleStepTests (https://github.com/SingleStepTests/sm83)
+// against the CPU on flat RAM, which is all the tests cover. The JSON files
+// aren't distributed with the emulator; copy them into tests/sm83/, e.g.
+// tests/sm83/v1/00.json, and run this with `cargo test -- --ignored`.
+
+use missingnogmb::bus::{Access, Bus, RamBus, Recorder};
+use missingnogmb::cpu::{Cpu, Registers};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+fn registers(state: &Value) -> Registers {
+    let reg = |name: &str| state[name].as_u64().unwrap();
+    Registers {
+        a: reg("a") as u8,
+        f: reg("f") as u8,
+        b: reg("b") as u8,
+        c: reg("c") as u8,
+        d: reg("d") as u8,
+        e: reg("e") as u8,
+        h: reg("h") as u8,
+        l: reg("l") as u8,
+        sp: reg("sp") as u16,
+        pc: reg("pc") as u16,
+    }
+}
+
+fn ram(state: &Value) -> impl Iterator<Item = (u16, u8)> + '_ {
+    state["ram"].as_array().unwrap().iter().map(|entry| {
+        (
+            entry[0].as_u64().unwrap() as u16,
+            entry[1].as_u64().unwrap() as u8,
+        )
+    })
+}
+
+// Bus activity is given as [address, value, "r-m"], with "-wm" for writes and
+// "---" (or null) for internal cycles
+fn accesses(cycles: &Value) -> Vec<Access> {
+    cycles
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|cycle| {
+            let address = cycle[0].as_u64().unwrap_or(0) as u16;
+            let val = cycle[1].as_u64().unwrap_or(0) as u8;
+            match cycle[2].as_str() {
+                Some(kind) if kind.starts_with('r') => Access::Read(address, val),
+                Some(kind) if kind.contains('w') => Access::Write(address, val),
+                _ => Access::Internal,
+            }
+        })
+        .collect()
+}
+
+// Returns a description of the first difference, if any
+fn run_case(case: &Value) -> Result<(), String> {
+    let (initial, expected) = (&case["initial"], &case["final"]);
+
+    let mut bus = Recorder::new(RamBus::new());
+    for (address, val) in ram(initial) {
+        bus.bus.load(address, &[val]);
+    }
+
+    let mut cpu = Cpu::new(0);
+    cpu.set_registers(registers(initial));
+    cpu.set_ime(initial["ime"].as_u64() == Some(1));
+    cpu.step(&mut bus);
+
+    if cpu.registers() != registers(expected) {
+        return Err(format!(
+            "registers\n  expected {:?}\n  got      {:?}",
+            registers(expected),
+            cpu.registers()
+        ));
+    }
+    if cpu.ime() != (expected["ime"].as_u64() == Some(1)) {
+        return Err(format!(
+            "ime: expected {}, got {}",
+            expected["ime"],
+            cpu.ime() as u8
+        ));
+    }
+    // Not every test gives the pending EI
+    if let Some(ei) = expected["ei"].as_u64() {
+        if cpu.ime_scheduled() != (ei == 1) {
+            return Err(format!(
+                "ei: expected {}, got {}",
+                ei,
+                cpu.ime_scheduled() as u8
+            ));
+        }
+    }
+    for (address, val) in ram(expected) {
+        if bus.peek(address) != val {
+            return Err(format!(
+                "ram at {:04x}: expected {:02x}, got {:02x}",
+                address,
+                val,
+                bus.peek(address)
+            ));
+        }
+    }
+    if accesses(&case["cycles"]) != bus.accesses {
+        return Err(format!(
+            "bus activity\n  expected {:?}\n  got      {:?}",
+            accesses(&case["cycles"]),
+            bus.accesses
+        ));
+    }
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs the JSON tests in tests/sm83/v1/"]
+fn sm83() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/sm83/v1");
+    let entries =
+        fs::read_dir(&dir).unwrap_or_else(|err| panic!("no tests in {}: {}", dir.display(), err));
+
+    let mut files: Vec<_> = entries
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    assert!(!files.is_empty(), "no tests in {}", dir.display());
+
+    let mut failures = Vec::new();
+    for file in files {
+        let cases: Value = serde_json::from_slice(&fs::read(&file).unwrap()).unwrap();
+        let cases = cases.as_array().unwrap();
+
+        // Report only the first failing case for each opcode
+        let failed = cases.iter().find_map(|case| {
+            run_case(case)
+                .err()
+                .map(|error| format!("{}: {}", case["name"].as_str().unwrap_or("?"), error))
+        });
+
+        let name = file.file_stem().unwrap().to_string_lossy();
+        println!(
+            "{:<8} {}",
+            name,
+            if failed.is_none() { "ok" } else { "FAILED" }
+        );
+        failures.extend(failed);
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,51 @@
+// Checks trace lines against the format gameboy-doctor compares logs in
+
+mod common;
+
+use missingnogmb::gameboy::Gameboy;
+use std::fs;
+
+#[test]
+fn first_line_matches_gameboy_doctor() {
+    // The entry point jumps to 0x150, which loops forever
+    let mut rom = common::rom(0x00, 0x8000, 0);
+    rom[0x150..0x152].copy_from_slice(&[0x18, 0xfe]);
+    // A non-zero header checksum leaves the boot ROM with H and C set
+    assert_ne!(rom[0x14d], 0);
+
+    let path = std::env::temp_dir().join(format!("missingnogmb-{}.log", std::process::id()));
+    let mut gb = Gameboy::new(rom).unwrap();
+    gb.trace_to(&path).unwrap();
+    for _ in 0..3 {
+        gb.step();
+    }
+    // Flushes the trace
+    drop(gb);
+
+    let log = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,50,01",
+            "A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0101 PCMEM:C3,50,01,CE",
+            "A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0150 PCMEM:18,FE,00,00",
+        ]
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn write_errors_stop_tracing() {
+    let mut rom = common::rom(0x00, 0x8000, 0);
+    rom[0x150..0x152].copy_from_slice(&[0x18, 0xfe]);
+
+    // Every write fails once the buffer fills up
+    let mut gb = Gameboy::new(rom).unwrap();
+    gb.trace_to("/dev/full".as_ref()).unwrap();
+    for _ in 0..1000 {
+        gb.step();
+    }
+    assert_eq!(gb.registers().pc, 0x150);
+}